@@ -0,0 +1,52 @@
+//! Move generation throughput: random playouts through `valid_moves`, `make_move` and
+//! `result_value`, the calls every expanded search node makes. Only uses API that predates the
+//! bitboard board, so the numbers compare across revisions.
+//!
+//! ```text
+//! cargo bench --bench movegen
+//! ```
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use rand::rngs::SmallRng;
+use rand::{Rng, SeedableRng};
+use santorini_wasm::{SantoriniBoard, ACTION_SIZE};
+
+const GAMES: usize = 20_000;
+const RUNS: usize = 5;
+
+/// Play `GAMES` uniformly random games and return the number of positions visited.
+fn playouts(seed: u64) -> u64 {
+    let mut rng = SmallRng::seed_from_u64(seed);
+    let mut mask = [false; ACTION_SIZE];
+    let mut actions = Vec::with_capacity(ACTION_SIZE);
+    let mut positions = 0;
+    for _ in 0..GAMES {
+        let mut board = SantoriniBoard::new().clone_internal();
+        let mut player = 0;
+        while black_box(&board).result_value(player).is_none() {
+            board.valid_moves(player, &mut mask);
+            actions.clear();
+            actions.extend((0..ACTION_SIZE).filter(|&action| mask[action]));
+            player = board.make_move(actions[rng.gen_range(0..actions.len())], player);
+            positions += 1;
+        }
+    }
+    positions
+}
+
+fn main() {
+    let positions = playouts(1);
+    let mut best = f64::MAX;
+    for _ in 0..RUNS {
+        let start = Instant::now();
+        black_box(playouts(1));
+        best = best.min(start.elapsed().as_secs_f64());
+    }
+    println!(
+        "movegen: {positions} positions in {:.1} ms (best of {RUNS}), {:.2} M positions/s",
+        best * 1e3,
+        positions as f64 / best / 1e6
+    );
+}
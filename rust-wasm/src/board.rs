@@ -1,6 +1,11 @@
 use serde::{Deserialize, Serialize};
 use wasm_bindgen::prelude::*;
 
+use crate::error::{BoardViolation, SantoriniError};
+use crate::moves::{LegalMoves, Move, Square};
+use crate::tables::{direction_between, step, NEIGHBOURS};
+use crate::zobrist;
+
 pub const BOARD_SIZE: usize = 5;
 pub const CHANNELS: usize = 3;
 pub const CELL_COUNT: usize = BOARD_SIZE * BOARD_SIZE;
@@ -19,23 +24,16 @@ pub type StateSize = usize;
 /// Exported for TypeScript bindings: total number of actions.
 pub type ActionSize = usize;
 
-const DIRECTIONS: [(i8, i8); 9] = [
-    (-1, -1),
-    (-1, 0),
-    (-1, 1),
-    (0, -1),
-    (0, 0),
-    (0, 1),
-    (1, -1),
-    (1, 0),
-    (1, 1),
-];
-
 #[inline]
 const fn idx(y: usize, x: usize) -> usize {
     y * BOARD_SIZE + x
 }
 
+#[inline]
+const fn bit(square: usize) -> u32 {
+    1 << square
+}
+
 #[inline]
 pub const fn encode_action(worker: usize, move_direction: usize, build_direction: usize) -> usize {
     let worker_offset = NB_GODS * 9 * 9 * worker;
@@ -53,11 +51,38 @@ pub const fn decode_action(action: usize) -> (usize, usize, usize) {
     (worker, move_direction, build_direction)
 }
 
-#[derive(Clone, Copy, Serialize, Deserialize)]
+/// Position stored as 25-bit masks (bit `i` = square `i`, row-major) for move generation, plus a
+/// per-square mirror of the tensor channels for serialisation and single-square lookups. Every
+/// mutation goes through helpers that keep both views in lockstep, and serde goes through the
+/// validated 75-byte tensor so the masks are always rebuilt from the mirror.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(into = "Vec<i8>", try_from = "Vec<i8>")]
 pub struct BoardState {
     workers: [i8; CELL_COUNT],
     levels: [i8; CELL_COUNT],
     round: u16,
+    /// One mask per worker slot, in `1, 2, -1, -2` order (see `worker_slot`).
+    worker_masks: [u32; 4],
+    /// `height_masks[i]` marks squares with level `> i`, so index 3 holds the domes.
+    height_masks: [u32; 4],
+    /// Player expected to move next as far as this board knows: updated by `make_move`, reset to
+    /// 0 by `canonicalised` (the perspective player always moves) and by decoding.
+    side_to_move: u8,
+    /// Zobrist key over workers, levels and `side_to_move`, updated incrementally.
+    hash: u64,
+}
+
+/// Everything [`BoardState::unmake_move`] needs to take back one move. Squares are flattened
+/// indices; `from`/`build` are `None` for placements.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UndoInfo {
+    worker: i8,
+    from: Option<usize>,
+    to: usize,
+    build: Option<usize>,
+    previous_level: i8,
+    previous_round: u16,
+    previous_side: u8,
 }
 
 impl BoardState {
@@ -66,6 +91,10 @@ impl BoardState {
             workers: [0; CELL_COUNT],
             levels: [0; CELL_COUNT],
             round: 0,
+            worker_masks: [0; 4],
+            height_masks: [0; 4],
+            side_to_move: 0,
+            hash: 0,
         }
     }
 
@@ -79,11 +108,16 @@ impl BoardState {
         flat
     }
 
+    /// Panicking counterpart of [`BoardState::try_from_bytes`] for trusted buffers.
     pub fn from_bytes(bytes: &[i8]) -> Self {
-        assert!(
-            bytes.len() == STATE_SIZE,
-            "expected 75 entries for board state"
-        );
+        Self::try_from_bytes(bytes).unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// Decode a 75-entry tensor, rejecting buffers of the wrong length.
+    pub fn try_from_bytes(bytes: &[i8]) -> Result<Self, SantoriniError> {
+        if bytes.len() != STATE_SIZE {
+            return Err(SantoriniError::InvalidStateLength { len: bytes.len() });
+        }
         let mut state = Self::new();
         let mut cursor = 0;
         for i in 0..CELL_COUNT {
@@ -94,110 +128,390 @@ impl BoardState {
             }
             cursor += CHANNELS;
         }
-        state
+        state.rebuild_caches();
+        Ok(state)
     }
 
-    pub fn canonicalised(&self, player: usize) -> Self {
-        if player == 0 {
-            *self
+    /// Check every structural invariant of the position and return all violations (empty when
+    /// the board is sound). Boards decoded from untrusted storage should pass this before being
+    /// searched.
+    pub fn violations(&self) -> Vec<BoardViolation> {
+        let mut violations = Vec::new();
+        let mut seen: [Option<usize>; 4] = [None; 4];
+        for square in 0..CELL_COUNT {
+            let worker = self.workers[square];
+            let level = self.levels[square];
+            if !(0..=4).contains(&level) {
+                violations.push(BoardViolation::InvalidLevel { square, level });
+            }
+            if worker == 0 {
+                continue;
+            }
+            let Some(slot) = worker_slot(worker) else {
+                violations.push(BoardViolation::InvalidWorker {
+                    square,
+                    value: worker,
+                });
+                continue;
+            };
+            if level >= 4 {
+                violations.push(BoardViolation::WorkerOnDome { square, worker });
+            }
+            match seen[slot] {
+                Some(first) => violations.push(BoardViolation::DuplicateWorker {
+                    worker,
+                    first,
+                    second: square,
+                }),
+                None => seen[slot] = Some(square),
+            }
+        }
+        // Player 0 places both workers before player 1 places any, each side `1` before `2`.
+        let first_unplaced = seen[..2].iter().any(Option::is_none);
+        let second_placed = seen[2..].iter().any(Option::is_some);
+        if seen[0].is_none() && seen[1].is_some() {
+            violations.push(BoardViolation::PlacementOrder { player: 0 });
+        }
+        if (seen[2].is_none() && seen[3].is_some()) || (first_unplaced && second_placed) {
+            violations.push(BoardViolation::PlacementOrder { player: 1 });
+        }
+        violations
+    }
+
+    /// [`BoardState::violations`] folded into a single [`SantoriniError::InvalidBoard`].
+    pub fn validate(&self) -> Result<(), SantoriniError> {
+        let violations = self.violations();
+        if violations.is_empty() {
+            Ok(())
         } else {
-            let mut clone = *self;
+            Err(SantoriniError::InvalidBoard { violations })
+        }
+    }
+
+    /// Board seen from `player`'s perspective (their workers become the positive ids). The
+    /// perspective player is assumed to be the one about to move, so the copy has player 0 to
+    /// move.
+    pub fn canonicalised(&self, player: usize) -> Self {
+        let mut clone = *self;
+        if player != 0 {
             for w in &mut clone.workers {
                 *w = -*w;
             }
-            clone
+            let [own_1, own_2, other_1, other_2] = self.worker_masks;
+            clone.worker_masks = [other_1, other_2, own_1, own_2];
+            clone.hash ^= self.workers_hash() ^ clone.workers_hash();
         }
+        clone.set_side_to_move(0);
+        clone
     }
 
     pub fn valid_moves(&self, player: usize, out: &mut [bool; ACTION_SIZE]) {
         out.fill(false);
 
         if let Some((_placement_player, _worker_to_place)) = self.next_placement() {
-            for index in 0..CELL_COUNT {
-                out[index] = self.workers[index] == 0;
+            for (flag, &worker) in out.iter_mut().zip(self.workers.iter()) {
+                *flag = worker == 0;
             }
             return;
         }
 
-        let player_sign = if player == 0 { 1 } else { -1 };
+        let occupied = self.occupied();
+        let domes = self.height_masks[3];
         for worker in 0..2 {
-            let worker_id = (worker as i8 + 1) * player_sign;
-            let Some(position) = self.find_worker(worker_id) else {
+            let worker_mask = self.worker_masks[player_slots(player)[worker]];
+            if worker_mask == 0 {
                 continue;
-            };
-
-            for move_direction in 0..9 {
-                if move_direction == 4 {
-                    continue; // NO_MOVE
-                }
-                let Some(target) = apply_direction(position, move_direction) else {
-                    continue;
-                };
-                if !self.can_move(position, target) {
-                    continue;
-                }
-                for build_direction in 0..9 {
-                    if build_direction == 4 {
-                        continue; // NO_BUILD
-                    }
-                    let Some(build_pos) = apply_direction(target, build_direction) else {
-                        continue;
-                    };
-                    if !self.can_build(build_pos, worker_id) {
-                        continue;
-                    }
-                    let action = encode_action(worker, move_direction, build_direction);
+            }
+            let from = worker_mask.trailing_zeros() as usize;
+            // The worker's own square is vacated by the move, so it stays buildable.
+            let build_blockers = (occupied & !worker_mask) | domes;
+            let mut destinations = self.destinations_from(from, occupied);
+            while destinations != 0 {
+                let to = destinations.trailing_zeros() as usize;
+                destinations &= destinations - 1;
+                let move_direction = direction_between(from, to);
+                let mut builds = NEIGHBOURS[to] & !build_blockers;
+                while builds != 0 {
+                    let build = builds.trailing_zeros() as usize;
+                    builds &= builds - 1;
+                    let action =
+                        encode_action(worker, move_direction, direction_between(to, build));
                     out[action] = true;
                 }
             }
         }
     }
 
-    pub fn make_move(&mut self, action: usize, player: usize) -> usize {
-        if let Some((placement_player, worker_to_place)) = self.next_placement() {
-            assert!(action < PLACEMENT_ACTIONS, "placement indices must be < 25");
-            if placement_player != player {
-                panic!("player {player} attempted to place worker for player {placement_player}");
+    /// A move of `player` that wins on the spot by climbing onto level 3, if there is one. It
+    /// builds back on the vacated square, which is always legal.
+    pub fn winning_move(&self, player: usize) -> Option<usize> {
+        if self.next_placement().is_some() {
+            return None;
+        }
+        let occupied = self.occupied();
+        let level_three = self.height_masks[2] & !self.height_masks[3];
+        for worker in 0..2 {
+            let worker_mask = self.worker_masks[player_slots(player)[worker]];
+            if worker_mask == 0 {
+                continue;
             }
-            let y = action / BOARD_SIZE;
-            let x = action % BOARD_SIZE;
-            if self.workers[idx(y, x)] != 0 {
-                panic!("cannot place worker on occupied square");
+            let from = worker_mask.trailing_zeros() as usize;
+            let climbs = self.destinations_from(from, occupied) & level_three;
+            if climbs != 0 {
+                let to = climbs.trailing_zeros() as usize;
+                return Some(encode_action(
+                    worker,
+                    direction_between(from, to),
+                    direction_between(to, from),
+                ));
             }
-            self.workers[idx(y, x)] = worker_to_place;
-            self.bump_round();
-            return match worker_to_place {
-                1 | -1 => placement_player,
-                _ => 1 - placement_player,
-            };
+        }
+        None
+    }
+
+    /// Iterate over the legal moves of `player` with their squares already decoded. Yields the
+    /// same actions, in the same order, as the flags set by [`BoardState::valid_moves`].
+    pub fn legal_moves(&self, player: usize) -> LegalMoves<'_> {
+        LegalMoves::new(self, player)
+    }
+
+    /// Decode `action` against this board: the placement square during setup, otherwise the
+    /// moving worker's current square plus its destination and build squares. Only geometry is
+    /// checked here; legality is left to [`BoardState::try_make_move`].
+    pub fn describe_action(&self, action: usize, player: usize) -> Result<Move, SantoriniError> {
+        if player > 1 {
+            return Err(SantoriniError::InvalidPlayer { player });
+        }
+        if action >= ACTION_SIZE {
+            return Err(SantoriniError::ActionOutOfRange { action });
+        }
+        if self.next_placement().is_some() {
+            if action >= PLACEMENT_ACTIONS {
+                return Err(SantoriniError::PlacementOutOfRange { action });
+            }
+            return Ok(Move::Placement {
+                action,
+                square: (action / BOARD_SIZE, action % BOARD_SIZE),
+            });
         }
 
         let (worker, move_direction, build_direction) = decode_action(action);
-        let player_sign = if player == 0 { 1 } else { -1 };
-        let worker_id = (worker as i8 + 1) * player_sign;
-        let old_pos = self
+        let worker_id = (worker as i8 + 1) * if player == 0 { 1 } else { -1 };
+        let from = self
             .find_worker(worker_id)
-            .unwrap_or_else(|| panic!("missing worker {worker_id} for player {player}"));
-        let target = apply_direction(old_pos, move_direction).expect("move direction off board");
+            .ok_or(SantoriniError::MissingWorker {
+                worker: worker_id,
+                player,
+            })?;
+        let to =
+            apply_direction(from, move_direction).ok_or(SantoriniError::MoveOffBoard { action })?;
+        let build =
+            apply_direction(to, build_direction).ok_or(SantoriniError::MoveOffBoard { action })?;
+        Ok(Move::Step {
+            action,
+            worker,
+            from,
+            to,
+            build,
+        })
+    }
 
-        let old_level = self.levels[idx(old_pos.0, old_pos.1)];
-        self.workers[idx(old_pos.0, old_pos.1)] = 0;
-        self.workers[idx(target.0, target.1)] = worker_id;
+    /// Encode the move of `player` described by squares and check that it is legal. During setup
+    /// `from` is the placement square and `to`/`build` must be `None`.
+    pub fn action_from_coords(
+        &self,
+        player: usize,
+        from: Square,
+        to: Option<Square>,
+        build: Option<Square>,
+    ) -> Result<usize, SantoriniError> {
+        if player > 1 {
+            return Err(SantoriniError::InvalidPlayer { player });
+        }
+        let illegal = SantoriniError::IllegalCoordinates { from, to, build };
+        let on_board = |(y, x): Square| (y < BOARD_SIZE && x < BOARD_SIZE).then(|| idx(y, x));
+        let action = if self.next_placement().is_some() {
+            match (on_board(from), to, build) {
+                (Some(square), None, None) => square,
+                _ => return Err(illegal),
+            }
+        } else {
+            let (Some(from), Some(to), Some(build)) = (
+                on_board(from),
+                to.and_then(on_board),
+                build.and_then(on_board),
+            ) else {
+                return Err(illegal);
+            };
+            let sign = if player == 0 { 1 } else { -1 };
+            let worker = match self.worker_at(from) {
+                id if id == sign => 0,
+                id if id == 2 * sign => 1,
+                _ => return Err(illegal),
+            };
+            if NEIGHBOURS[from] & bit(to) == 0 || NEIGHBOURS[to] & bit(build) == 0 {
+                return Err(illegal);
+            }
+            encode_action(
+                worker,
+                direction_between(from, to),
+                direction_between(to, build),
+            )
+        };
+        let mut probe = *self;
+        probe.try_make_move(action, player).map_err(|_| illegal)?;
+        Ok(action)
+    }
+
+    /// Squares of `player`'s workers that have at least one legal move (empty during setup).
+    pub fn movable_workers(&self, player: usize) -> Vec<Square> {
+        let mut squares: Vec<Square> = self
+            .legal_moves(player)
+            .filter_map(|mv| match mv {
+                Move::Step { from, .. } => Some(from),
+                Move::Placement { .. } => None,
+            })
+            .collect();
+        squares.dedup();
+        squares
+    }
+
+    /// Squares the worker standing on `from` may move to (each has at least one legal build).
+    pub fn legal_destinations(&self, player: usize, from: Square) -> Vec<Square> {
+        let mut squares: Vec<Square> = self
+            .legal_moves(player)
+            .filter_map(|mv| match mv {
+                Move::Step {
+                    from: start, to, ..
+                } if start == from => Some(to),
+                _ => None,
+            })
+            .collect();
+        squares.dedup();
+        squares
+    }
+
+    /// Squares the worker may build on after moving from `from` to `to`.
+    pub fn legal_builds(&self, player: usize, from: Square, to: Square) -> Vec<Square> {
+        self.legal_moves(player)
+            .filter_map(|mv| match mv {
+                Move::Step {
+                    from: start,
+                    to: target,
+                    build,
+                    ..
+                } if start == from && target == to => Some(build),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Panicking counterpart of [`BoardState::try_make_move`], used by the search where actions
+    /// always come from [`BoardState::valid_moves`].
+    pub fn make_move(&mut self, action: usize, player: usize) -> usize {
+        self.try_make_move(action, player)
+            .unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// Apply `action` for `player` and return the next player, or explain why the action is not
+    /// legal. The board is left untouched on error.
+    pub fn try_make_move(&mut self, action: usize, player: usize) -> Result<usize, SantoriniError> {
+        self.try_make_move_undoable(action, player)
+            .map(|(next_player, _)| next_player)
+    }
 
-        if build_direction != 4 {
-            if let Some(build_pos) = apply_direction(target, build_direction) {
-                let index = idx(build_pos.0, build_pos.1);
-                self.levels[index] = (self.levels[index] + 1).min(4);
+    /// Like [`BoardState::try_make_move`], additionally returning the [`UndoInfo`] needed by
+    /// [`BoardState::unmake_move`] to restore the current position exactly.
+    pub fn try_make_move_undoable(
+        &mut self,
+        action: usize,
+        player: usize,
+    ) -> Result<(usize, UndoInfo), SantoriniError> {
+        let previous_round = self.round;
+        let previous_side = self.side_to_move;
+        match self.describe_action(action, player)? {
+            Move::Placement { square, .. } => {
+                let (placement_player, worker_to_place) = self
+                    .next_placement()
+                    .expect("placements are only described during setup");
+                if placement_player != player {
+                    return Err(SantoriniError::WrongPlacementPlayer {
+                        player,
+                        expected: placement_player,
+                    });
+                }
+                let index = idx(square.0, square.1);
+                if self.workers[index] != 0 {
+                    return Err(SantoriniError::SquareOccupied { square: index });
+                }
+                self.place_worker(index, worker_to_place);
+                self.bump_round();
+                let next_player = match worker_to_place {
+                    1 | -1 => placement_player,
+                    _ => 1 - placement_player,
+                };
+                self.set_side_to_move(next_player as u8);
+                let undo = UndoInfo {
+                    worker: worker_to_place,
+                    from: None,
+                    to: index,
+                    build: None,
+                    previous_level: 0,
+                    previous_round,
+                    previous_side,
+                };
+                Ok((next_player, undo))
             }
-        }
+            Move::Step {
+                worker,
+                from,
+                to,
+                build,
+                ..
+            } => {
+                let worker_id = (worker as i8 + 1) * if player == 0 { 1 } else { -1 };
+                // NO_MOVE / NO_BUILD decode to a zero-length step.
+                if from == to || build == to {
+                    return Err(SantoriniError::IllegalMove { action, player });
+                }
+                let (from, to, build) =
+                    (idx(from.0, from.1), idx(to.0, to.1), idx(build.0, build.1));
+                if !self.can_move(from, to) || !self.can_build(build, worker_id) {
+                    return Err(SantoriniError::IllegalMove { action, player });
+                }
+
+                let previous_level = self.levels[build];
+                self.move_worker(from, to);
+                self.raise_level(build);
 
-        let new_level = self.levels[idx(target.0, target.1)];
-        if new_level >= 3 && new_level > old_level {
-            // Victory detected later by `result_value`; keep method branch for clarity.
+                self.bump_round();
+                self.set_side_to_move(1 - player as u8);
+                let undo = UndoInfo {
+                    worker: worker_id,
+                    from: Some(from),
+                    to,
+                    build: Some(build),
+                    previous_level,
+                    previous_round,
+                    previous_side,
+                };
+                Ok((1 - player, undo))
+            }
         }
+    }
 
-        self.bump_round();
-        1 - player
+    /// Revert the move recorded in `undo`. Undo records must be replayed in reverse order of the
+    /// moves that produced them.
+    pub fn unmake_move(&mut self, undo: UndoInfo) {
+        if let Some(build) = undo.build {
+            self.lower_level(build, undo.previous_level);
+        }
+        match undo.from {
+            Some(from) => self.move_worker(undo.to, from),
+            None => self.remove_worker(undo.to),
+        }
+        self.round = undo.previous_round;
+        self.set_side_to_move(undo.previous_side);
     }
 
     pub fn result_value(&self, next_player: usize) -> Option<f32> {
@@ -221,11 +535,12 @@ impl BoardState {
     }
 
     pub fn score_for(&self, player: usize) -> i8 {
-        let compare: fn(i8) -> bool = if player == 0 { |w| w > 0 } else { |w| w < 0 };
         let mut highest = 0;
-        for cell in 0..CELL_COUNT {
-            if compare(self.workers[cell]) {
-                highest = highest.max(self.levels[cell]);
+        for slot in player_slots(player) {
+            let mut mask = self.worker_masks[slot];
+            while mask != 0 {
+                highest = highest.max(self.levels[mask.trailing_zeros() as usize]);
+                mask &= mask - 1;
             }
         }
         highest
@@ -242,7 +557,7 @@ impl BoardState {
         }
     }
 
-    pub fn to_vec(&self) -> Vec<i8> {
+    pub fn to_vec(self) -> Vec<i8> {
         let mut vec = vec![0; STATE_SIZE];
         self.write_into_slice(&mut vec);
         vec
@@ -252,66 +567,181 @@ impl BoardState {
         Self::from_bytes(data)
     }
 
-    pub fn key(&self) -> [i8; STATE_SIZE] {
-        self.as_bytes()
+    /// 64-bit Zobrist key of the position (workers, levels, side to move). The round counter is
+    /// deliberately left out, so the same position reached at different rounds shares one key.
+    pub fn hash(&self) -> u64 {
+        self.hash
+    }
+
+    /// Whether `other` is the same position as far as [`BoardState::hash`] is concerned, i.e.
+    /// equal in everything but the round counter.
+    pub fn same_position(&self, other: &Self) -> bool {
+        self.workers == other.workers
+            && self.levels == other.levels
+            && self.side_to_move == other.side_to_move
     }
 
     fn has_any_valid_move(&self, player: usize) -> bool {
-        let mut mask = [false; ACTION_SIZE];
-        self.valid_moves(player, &mut mask);
-        mask.iter().any(|&flag| flag)
+        if self.next_placement().is_some() {
+            return self.occupied() != (1 << CELL_COUNT) - 1;
+        }
+        // A legal destination always admits a build: the square the worker just left.
+        let occupied = self.occupied();
+        player_slots(player)
+            .into_iter()
+            .map(|slot| self.worker_masks[slot])
+            .filter(|&mask| mask != 0)
+            .any(|mask| self.destinations_from(mask.trailing_zeros() as usize, occupied) != 0)
+    }
+
+    /// Squares a worker standing on `from` may step to: adjacent, free, not domed, and at most
+    /// one level higher.
+    #[inline]
+    fn destinations_from(&self, from: usize, occupied: u32) -> u32 {
+        let height = self.levels[from].clamp(0, 3) as usize;
+        let too_high = if height < 3 {
+            self.height_masks[height + 1]
+        } else {
+            0
+        };
+        NEIGHBOURS[from] & !occupied & !self.height_masks[3] & !too_high
+    }
+
+    #[inline]
+    fn occupied(&self) -> u32 {
+        self.worker_masks[0] | self.worker_masks[1] | self.worker_masks[2] | self.worker_masks[3]
+    }
+
+    /// Recompute masks and hash from the per-square mirror.
+    fn rebuild_caches(&mut self) {
+        self.worker_masks = [0; 4];
+        self.height_masks = [0; 4];
+        let mut level_hash = 0;
+        for square in 0..CELL_COUNT {
+            if let Some(slot) = worker_slot(self.workers[square]) {
+                self.worker_masks[slot] |= bit(square);
+            }
+            for (height, mask) in self.height_masks.iter_mut().enumerate() {
+                if self.levels[square] > height as i8 {
+                    *mask |= bit(square);
+                }
+            }
+            level_hash ^= zobrist::level_key(self.levels[square], square);
+        }
+        self.hash = level_hash ^ self.workers_hash();
+        if self.side_to_move != 0 {
+            self.hash ^= zobrist::SIDE_KEY;
+        }
     }
 
-    fn next_placement(&self) -> Option<(usize, i8)> {
-        if self.find_worker(1).is_none() {
-            return Some((0, 1));
+    /// Worker contribution to the Zobrist key, read from the masks.
+    fn workers_hash(&self) -> u64 {
+        let mut hash = 0;
+        for (slot, &mask) in self.worker_masks.iter().enumerate() {
+            let mut mask = mask;
+            while mask != 0 {
+                hash ^= zobrist::worker_key(slot, mask.trailing_zeros() as usize);
+                mask &= mask - 1;
+            }
         }
-        if self.find_worker(2).is_none() {
-            return Some((0, 2));
+        hash
+    }
+
+    fn set_side_to_move(&mut self, player: u8) {
+        if self.side_to_move != player {
+            self.hash ^= zobrist::SIDE_KEY;
+            self.side_to_move = player;
         }
-        if self.find_worker(-1).is_none() {
-            return Some((1, -1));
+    }
+
+    fn place_worker(&mut self, square: usize, worker: i8) {
+        self.workers[square] = worker;
+        if let Some(slot) = worker_slot(worker) {
+            self.worker_masks[slot] |= bit(square);
+            self.hash ^= zobrist::worker_key(slot, square);
         }
-        if self.find_worker(-2).is_none() {
-            return Some((1, -2));
+    }
+
+    fn remove_worker(&mut self, square: usize) {
+        if let Some(slot) = worker_slot(self.workers[square]) {
+            self.worker_masks[slot] &= !bit(square);
+            self.hash ^= zobrist::worker_key(slot, square);
         }
-        None
+        self.workers[square] = 0;
     }
 
-    fn find_worker(&self, worker: i8) -> Option<(usize, usize)> {
-        for y in 0..BOARD_SIZE {
-            for x in 0..BOARD_SIZE {
-                if self.workers[idx(y, x)] == worker {
-                    return Some((y, x));
-                }
+    fn move_worker(&mut self, from: usize, to: usize) {
+        let worker = self.workers[from];
+        self.remove_worker(from);
+        self.place_worker(to, worker);
+    }
+
+    fn raise_level(&mut self, square: usize) {
+        let level = self.levels[square];
+        if (0..4).contains(&level) {
+            self.height_masks[level as usize] |= bit(square);
+        }
+        let raised = (level + 1).min(4);
+        self.hash ^= zobrist::level_key(level, square) ^ zobrist::level_key(raised, square);
+        self.levels[square] = raised;
+    }
+
+    fn lower_level(&mut self, square: usize, previous_level: i8) {
+        for (height, mask) in self.height_masks.iter_mut().enumerate() {
+            if previous_level <= height as i8 {
+                *mask &= !bit(square);
             }
         }
-        None
+        self.hash ^= zobrist::level_key(self.levels[square], square)
+            ^ zobrist::level_key(previous_level, square);
+        self.levels[square] = previous_level;
+    }
+
+    /// Raw worker id at a flattened square index.
+    pub(crate) fn worker_at(&self, square: usize) -> i8 {
+        self.workers[square]
+    }
+
+    pub(crate) fn next_placement(&self) -> Option<(usize, i8)> {
+        const ORDER: [(usize, i8); 4] = [(0, 1), (0, 2), (1, -1), (1, -2)];
+        self.worker_masks
+            .iter()
+            .zip(ORDER)
+            .find_map(|(&mask, next)| (mask == 0).then_some(next))
+    }
+
+    pub(crate) fn find_worker(&self, worker: i8) -> Option<(usize, usize)> {
+        let square = self.worker_square(worker)?;
+        Some((square / BOARD_SIZE, square % BOARD_SIZE))
     }
 
-    fn can_move(&self, old_pos: (usize, usize), new_pos: (usize, usize)) -> bool {
-        if old_pos == new_pos {
+    /// Flattened square of `worker`, read straight from its mask.
+    #[inline]
+    pub(crate) fn worker_square(&self, worker: i8) -> Option<usize> {
+        let mask = self.worker_masks[worker_slot(worker)?];
+        (mask != 0).then(|| mask.trailing_zeros() as usize)
+    }
+
+    pub(crate) fn can_move(&self, from: usize, to: usize) -> bool {
+        if from == to {
             return true;
         }
-        let target_index = idx(new_pos.0, new_pos.1);
-        if self.workers[target_index] != 0 {
+        if self.workers[to] != 0 {
             return false;
         }
-        let new_level = self.levels[target_index];
+        let new_level = self.levels[to];
         if new_level > 3 {
             return false;
         }
-        let old_level = self.levels[idx(old_pos.0, old_pos.1)];
-        new_level <= old_level + 1
+        new_level <= self.levels[from] + 1
     }
 
-    fn can_build(&self, pos: (usize, usize), ignore: i8) -> bool {
-        let index = idx(pos.0, pos.1);
-        let occupant = self.workers[index];
+    pub(crate) fn can_build(&self, square: usize, ignore: i8) -> bool {
+        let occupant = self.workers[square];
         if occupant != 0 && occupant != ignore {
             return false;
         }
-        self.levels[index] < 4
+        self.levels[square] < 4
     }
 
     fn bump_round(&mut self) {
@@ -327,21 +757,70 @@ impl Default for BoardState {
     }
 }
 
-fn apply_direction(position: (usize, usize), direction: usize) -> Option<(usize, usize)> {
-    let delta = DIRECTIONS[direction];
-    let ny = position.0 as i8 + delta.0;
-    let nx = position.1 as i8 + delta.1;
-    if (0..BOARD_SIZE as i8).contains(&ny) && (0..BOARD_SIZE as i8).contains(&nx) {
-        Some((ny as usize, nx as usize))
+impl From<BoardState> for Vec<i8> {
+    fn from(state: BoardState) -> Self {
+        state.to_vec()
+    }
+}
+
+impl TryFrom<Vec<i8>> for BoardState {
+    type Error = SantoriniError;
+
+    /// Deserialised boards come from storage, so they must pass [`BoardState::validate`] too.
+    fn try_from(bytes: Vec<i8>) -> Result<Self, SantoriniError> {
+        let state = Self::try_from_bytes(&bytes)?;
+        state.validate()?;
+        Ok(state)
+    }
+}
+
+/// Map worker ids `1, 2, -1, -2` to slots `0..4`.
+#[inline]
+fn worker_slot(worker: i8) -> Option<usize> {
+    match worker {
+        1 => Some(0),
+        2 => Some(1),
+        -1 => Some(2),
+        -2 => Some(3),
+        _ => None,
+    }
+}
+
+/// Worker slots owned by `player` (any non-zero index is treated as player 1, as elsewhere).
+#[inline]
+const fn player_slots(player: usize) -> [usize; 2] {
+    if player == 0 {
+        [0, 1]
     } else {
-        None
+        [2, 3]
     }
 }
 
+pub(crate) fn apply_direction(
+    position: (usize, usize),
+    direction: usize,
+) -> Option<(usize, usize)> {
+    let target = step(idx(position.0, position.1), direction)?;
+    Some((target / BOARD_SIZE, target % BOARD_SIZE))
+}
+
+/// Deserialise optional `[y, x]` pairs coming from JavaScript (`undefined`/`null` map to `None`).
+fn squares_from_js(
+    from: JsValue,
+    to: JsValue,
+    build: JsValue,
+) -> Result<(Square, Option<Square>, Option<Square>), JsValue> {
+    let from: Square = serde_wasm_bindgen::from_value(from)?;
+    let to: Option<Square> = serde_wasm_bindgen::from_value(to)?;
+    let build: Option<Square> = serde_wasm_bindgen::from_value(build)?;
+    Ok((from, to, build))
+}
+
 /// A thin wasm-bindgen friendly board wrapper.
 #[wasm_bindgen]
 pub struct SantoriniBoard {
     state: BoardState,
+    history: Vec<UndoInfo>,
 }
 
 #[wasm_bindgen]
@@ -350,6 +829,7 @@ impl SantoriniBoard {
     pub fn new() -> SantoriniBoard {
         SantoriniBoard {
             state: BoardState::new(),
+            history: Vec::new(),
         }
     }
 
@@ -359,16 +839,31 @@ impl SantoriniBoard {
         self.state.to_vec()
     }
 
-    /// Replace the board contents from a 75-entry `Int8Array`.
+    /// Replace the board contents from a 75-entry `Int8Array`. Throws a `SantoriniError` (with a
+    /// `code` property) when the buffer has the wrong length.
     #[wasm_bindgen(js_name = setState)]
-    pub fn set_state(&mut self, data: Vec<i8>) {
-        assert_eq!(data.len(), STATE_SIZE, "state vectors must be length 75");
-        self.state = BoardState::from_vec(&data);
+    pub fn set_state(&mut self, data: Vec<i8>) -> Result<(), JsValue> {
+        self.state = BoardState::try_from_bytes(&data)?;
+        self.history.clear();
+        Ok(())
+    }
+
+    /// List every invariant the current board breaks as `{ code, message, square }` records. An
+    /// empty array means the position is safe to hand to `SantoriniMcts.search`.
+    pub fn validate(&self) -> Result<JsValue, JsValue> {
+        let reports: Vec<_> = self
+            .state
+            .violations()
+            .iter()
+            .map(BoardViolation::report)
+            .collect();
+        serde_wasm_bindgen::to_value(&reports).map_err(JsValue::from)
     }
 
     /// Reset all pieces, levels and round counter.
     pub fn reset(&mut self) {
         self.state.reset();
+        self.history.clear();
     }
 
     /// Return the zero-sum evaluation for the current position, if terminal.
@@ -385,11 +880,110 @@ impl SantoriniBoard {
         mask.iter().map(|flag| u8::from(*flag)).collect()
     }
 
+    /// List the legal moves of `player` as plain objects: `{ kind: "placement", action, square }`
+    /// during setup, `{ kind: "step", action, worker, from, to, build }` afterwards. Squares are
+    /// `[y, x]` pairs.
+    #[wasm_bindgen(js_name = legalMoves)]
+    pub fn legal_moves(&self, player: u8) -> Result<JsValue, JsValue> {
+        let moves: Vec<Move> = self.state.legal_moves(player as usize).collect();
+        serde_wasm_bindgen::to_value(&moves).map_err(JsValue::from)
+    }
+
+    /// Squares (`[y, x]`) of `player`'s workers that can make at least one legal move.
+    #[wasm_bindgen(js_name = movableWorkers)]
+    pub fn movable_workers(&self, player: u8) -> Result<JsValue, JsValue> {
+        let squares = self.state.movable_workers(player as usize);
+        serde_wasm_bindgen::to_value(&squares).map_err(JsValue::from)
+    }
+
+    /// Legal destination squares for the worker standing on `from`.
+    #[wasm_bindgen(js_name = legalDestinations)]
+    pub fn legal_destinations(&self, from: JsValue, player: u8) -> Result<JsValue, JsValue> {
+        let from: Square = serde_wasm_bindgen::from_value(from)?;
+        let squares = self.state.legal_destinations(player as usize, from);
+        serde_wasm_bindgen::to_value(&squares).map_err(JsValue::from)
+    }
+
+    /// Legal build squares once the worker on `from` has moved to `to`.
+    #[wasm_bindgen(js_name = legalBuilds)]
+    pub fn legal_builds(&self, from: JsValue, to: JsValue, player: u8) -> Result<JsValue, JsValue> {
+        let from: Square = serde_wasm_bindgen::from_value(from)?;
+        let to: Square = serde_wasm_bindgen::from_value(to)?;
+        let squares = self.state.legal_builds(player as usize, from, to);
+        serde_wasm_bindgen::to_value(&squares).map_err(JsValue::from)
+    }
+
+    /// Encode a move given as `[y, x]` squares into its action index, throwing a `SantoriniError`
+    /// when the squares do not form a legal move for `player`. During setup only `from` (the
+    /// placement square) is used and `to`/`build` must be omitted.
+    #[wasm_bindgen(js_name = actionFromCoords)]
+    pub fn action_from_coords(
+        &self,
+        from: JsValue,
+        to: JsValue,
+        build: JsValue,
+        player: u8,
+    ) -> Result<u16, JsValue> {
+        let (from, to, build) = squares_from_js(from, to, build)?;
+        let action = self
+            .state
+            .action_from_coords(player as usize, from, to, build)?;
+        Ok(action as u16)
+    }
+
+    /// Coordinate-based counterpart of `applyMove`: validate, encode and apply the move, then
+    /// return the next player. The board is unchanged when an error is thrown.
+    #[wasm_bindgen(js_name = applyMoveByCoords)]
+    pub fn apply_move_by_coords(
+        &mut self,
+        from: JsValue,
+        to: JsValue,
+        build: JsValue,
+        player: u8,
+    ) -> Result<u8, JsValue> {
+        let (from, to, build) = squares_from_js(from, to, build)?;
+        let action = self
+            .state
+            .action_from_coords(player as usize, from, to, build)?;
+        let (next, undo) = self.state.try_make_move_undoable(action, player as usize)?;
+        self.history.push(undo);
+        Ok(next as u8)
+    }
+
+    /// Decode `action` against the current board into the same object shape as `legalMoves`,
+    /// locating the moving worker's square on the board.
+    #[wasm_bindgen(js_name = coordsFromAction)]
+    pub fn coords_from_action(&self, action: u16, player: u8) -> Result<JsValue, JsValue> {
+        let mv = self
+            .state
+            .describe_action(action as usize, player as usize)?;
+        serde_wasm_bindgen::to_value(&mv).map_err(JsValue::from)
+    }
+
     /// Apply an action (placement or move) encoded in canonical action space and return the actual
-    /// next player index before canonicalisation.
+    /// next player index before canonicalisation. Illegal actions throw a `SantoriniError` and
+    /// leave the board unchanged.
     #[wasm_bindgen(js_name = applyMove)]
-    pub fn apply_move(&mut self, action: u16, player: u8) -> u8 {
-        self.state.make_move(action as usize, player as usize) as u8
+    pub fn apply_move(&mut self, action: u16, player: u8) -> Result<u8, JsValue> {
+        let (next, undo) = self
+            .state
+            .try_make_move_undoable(action as usize, player as usize)?;
+        self.history.push(undo);
+        Ok(next as u8)
+    }
+
+    /// Take back the most recent `applyMove`/`applyMoveByCoords` without re-sending the previous
+    /// board. Returns `false` when there is nothing to undo (history is cleared by `setState` and
+    /// `reset`).
+    #[wasm_bindgen(js_name = undoMove)]
+    pub fn undo_move(&mut self) -> bool {
+        match self.history.pop() {
+            Some(undo) => {
+                self.state.unmake_move(undo);
+                true
+            }
+            None => false,
+        }
     }
 
     /// Round counter (mirrors the Python implementation, capped to 127).
@@ -409,6 +1003,12 @@ impl SantoriniBoard {
     }
 }
 
+impl Default for SantoriniBoard {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl SantoriniBoard {
     pub fn clone_internal(&self) -> BoardState {
         self.state
@@ -446,4 +1046,224 @@ mod tests {
         assert_eq!(flipped.workers[idx(1, 1)], -1);
         assert_eq!(flipped.workers[idx(3, 3)], 1);
     }
+
+    #[test]
+    fn helper_mutations_roundtrip_through_the_tensor() {
+        let mut board = BoardState::new();
+        board.place_worker(idx(0, 0), 1);
+        board.place_worker(idx(4, 4), -2);
+        for _ in 0..3 {
+            board.raise_level(idx(2, 2));
+        }
+
+        // Decoding rebuilds masks and hash, so the whole state must match, not just the mirror.
+        let bytes: Vec<i8> = board.into();
+        assert_eq!(bytes[idx(2, 2) * CHANNELS + 1], 3);
+        assert_eq!(BoardState::from_bytes(&bytes), board);
+        assert_eq!(board.canonicalised(1).worker_at(idx(4, 4)), 2);
+
+        // The serde path validates, so a worker on a dome never deserialises.
+        let mut domed = bytes.clone();
+        domed[idx(4, 4) * CHANNELS + 1] = 4;
+        assert!(matches!(
+            BoardState::try_from(domed),
+            Err(SantoriniError::InvalidBoard { .. })
+        ));
+    }
+
+    #[test]
+    fn try_make_move_rejects_bad_input_without_mutating() {
+        let mut board = BoardState::new();
+        assert_eq!(
+            BoardState::try_from_bytes(&[0; 10]).err(),
+            Some(SantoriniError::InvalidStateLength { len: 10 })
+        );
+        assert_eq!(
+            board.try_make_move(3, 1),
+            Err(SantoriniError::WrongPlacementPlayer {
+                player: 1,
+                expected: 0
+            })
+        );
+        assert_eq!(board.try_make_move(3, 0), Ok(0));
+        let snapshot = board.as_bytes();
+        assert_eq!(
+            board.try_make_move(3, 0),
+            Err(SantoriniError::SquareOccupied { square: 3 })
+        );
+        assert_eq!(board.as_bytes(), snapshot);
+    }
+
+    #[test]
+    fn violations_report_every_broken_invariant() {
+        let mut board = BoardState::new();
+        board.workers[idx(0, 0)] = 1;
+        board.workers[idx(0, 1)] = 1;
+        board.workers[idx(1, 1)] = -2;
+        board.levels[idx(1, 1)] = 4;
+        board.workers[idx(2, 2)] = 3;
+        board.levels[idx(3, 3)] = 7;
+
+        let violations = board.violations();
+        assert_eq!(
+            violations,
+            vec![
+                BoardViolation::DuplicateWorker {
+                    worker: 1,
+                    first: idx(0, 0),
+                    second: idx(0, 1)
+                },
+                BoardViolation::WorkerOnDome {
+                    square: idx(1, 1),
+                    worker: -2
+                },
+                BoardViolation::InvalidWorker {
+                    square: idx(2, 2),
+                    value: 3
+                },
+                BoardViolation::InvalidLevel {
+                    square: idx(3, 3),
+                    level: 7
+                },
+                BoardViolation::PlacementOrder { player: 1 },
+            ]
+        );
+        assert!(BoardState::new().validate().is_ok());
+
+        // Player 1 may only start placing once both of player 0's workers are down.
+        let mut early = BoardState::new();
+        early.workers[idx(0, 0)] = 1;
+        early.workers[idx(4, 4)] = -1;
+        assert_eq!(
+            early.violations(),
+            vec![BoardViolation::PlacementOrder { player: 1 }]
+        );
+        early.workers[idx(0, 1)] = 2;
+        assert!(early.validate().is_ok());
+    }
+
+    #[test]
+    fn make_then_unmake_is_identity_for_every_legal_move() {
+        use rand::rngs::SmallRng;
+        use rand::{Rng, SeedableRng};
+
+        let mut rng = SmallRng::seed_from_u64(7);
+        for _game in 0..20 {
+            let mut board = BoardState::new();
+            let mut player = 0;
+            while board.result_value(player).is_none() {
+                let snapshot = board;
+                let moves: Vec<usize> = board.legal_moves(player).map(|mv| mv.action()).collect();
+                for &action in &moves {
+                    let (_, undo) = board
+                        .try_make_move_undoable(action, player)
+                        .expect("legal move applies");
+                    board.unmake_move(undo);
+                    assert_eq!(board, snapshot, "action {action} did not unmake cleanly");
+                }
+                let action = moves[rng.gen_range(0..moves.len())];
+                player = board.make_move(action, player);
+            }
+        }
+    }
+
+    #[test]
+    fn bitboard_generation_matches_square_rules() {
+        use rand::rngs::SmallRng;
+        use rand::{Rng, SeedableRng};
+
+        let mut rng = SmallRng::seed_from_u64(11);
+        for _game in 0..50 {
+            let mut board = BoardState::new();
+            let mut player = 0;
+            while board.result_value(player).is_none() {
+                let mut rebuilt = board;
+                rebuilt.rebuild_caches();
+                assert_eq!(
+                    rebuilt, board,
+                    "masks or hash drifted from the square mirror"
+                );
+
+                let mut mask = [false; ACTION_SIZE];
+                board.valid_moves(player, &mut mask);
+                let from_masks: Vec<usize> = (0..ACTION_SIZE).filter(|&a| mask[a]).collect();
+                let from_squares: Vec<usize> =
+                    board.legal_moves(player).map(|mv| mv.action()).collect();
+                assert_eq!(from_masks, from_squares);
+
+                let action = from_squares[rng.gen_range(0..from_squares.len())];
+                player = board.make_move(action, player);
+            }
+        }
+    }
+
+    #[test]
+    fn zobrist_hash_tracks_side_to_move_and_perspective() {
+        let mut board = BoardState::new();
+        for (action, player) in [(6, 0), (12, 0), (7, 1), (18, 1)] {
+            board.make_move(action, player);
+        }
+        let player = board.make_move(board.legal_moves(0).next().expect("move").action(), 0);
+        assert_eq!(player, 1);
+
+        // Same pieces, other side to move: a fresh board from the same bytes has player 0 to move.
+        let fresh = BoardState::from_bytes(&board.as_bytes());
+        assert_ne!(fresh.hash(), board.hash());
+        assert_eq!(fresh.hash() ^ crate::zobrist::SIDE_KEY, board.hash());
+
+        let canonical = board.canonicalised(1);
+        assert_eq!(
+            canonical.hash(),
+            BoardState::from_bytes(&canonical.as_bytes()).hash()
+        );
+    }
+
+    #[test]
+    fn hash_ignores_round_counter() {
+        let mut bytes = [0i8; STATE_SIZE];
+        for (square, worker) in [(6, 1), (12, 2), (7, -1), (18, -2)] {
+            bytes[square * 3] = worker;
+        }
+        bytes[13 * 3 + 1] = 2;
+        let early = BoardState::from_bytes(&bytes);
+        bytes[2] = 30;
+        let late = BoardState::from_bytes(&bytes);
+        assert_ne!(early, late);
+        assert!(early.same_position(&late));
+        assert_eq!(early.hash(), late.hash());
+    }
+
+    #[test]
+    fn winning_move_finds_every_immediate_climb() {
+        use rand::rngs::SmallRng;
+        use rand::{Rng, SeedableRng};
+
+        let mut rng = SmallRng::seed_from_u64(19);
+        let mut found = 0;
+        for _game in 0..50 {
+            let mut board = BoardState::new();
+            let mut player = 0;
+            while board.result_value(player).is_none() {
+                let moves: Vec<usize> = board.legal_moves(player).map(|mv| mv.action()).collect();
+                let climbs = moves.iter().any(|&action| {
+                    let mut next = board;
+                    next.make_move(action, player);
+                    next.score_for(player) == 3
+                });
+                match board.winning_move(player) {
+                    Some(action) => {
+                        found += 1;
+                        assert!(moves.contains(&action));
+                        let mut next = board;
+                        next.make_move(action, player);
+                        assert_eq!(next.score_for(player), 3);
+                    }
+                    None => assert!(!climbs),
+                }
+                let action = moves[rng.gen_range(0..moves.len())];
+                player = board.make_move(action, player);
+            }
+        }
+        assert!(found > 0);
+    }
 }
@@ -0,0 +1,62 @@
+//! Millisecond wall clock for time-budgeted search: `performance.now()` (falling back to
+//! `Date.now()`) inside the browser, [`std::time::Instant`] in native builds and tests. Only
+//! differences between readings are meaningful.
+//!
+//! Also home to [`yield_to_event_loop`], which long-running searches use to let JavaScript in.
+
+#[cfg(target_arch = "wasm32")]
+pub fn now_ms() -> f64 {
+    use wasm_bindgen::{JsCast, JsValue};
+
+    thread_local! {
+        static PERFORMANCE: Option<(JsValue, js_sys::Function)> = {
+            let global = js_sys::global();
+            let performance = js_sys::Reflect::get(&global, &JsValue::from_str("performance"))
+                .ok()
+                .filter(|value| value.is_object());
+            performance.and_then(|performance| {
+                let now = js_sys::Reflect::get(&performance, &JsValue::from_str("now")).ok()?;
+                Some((performance, now.dyn_into::<js_sys::Function>().ok()?))
+            })
+        };
+    }
+
+    PERFORMANCE.with(|performance| {
+        performance
+            .as_ref()
+            .and_then(|(target, now)| now.call0(target).ok()?.as_f64())
+            .unwrap_or_else(js_sys::Date::now)
+    })
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn now_ms() -> f64 {
+    use std::sync::OnceLock;
+    use std::time::Instant;
+
+    static EPOCH: OnceLock<Instant> = OnceLock::new();
+    EPOCH.get_or_init(Instant::now).elapsed().as_secs_f64() * 1000.0
+}
+
+/// Let the browser run other tasks (input events, `stop()` calls) before continuing, by waiting
+/// for a zero-delay `setTimeout`. Resolved promises alone are not enough: their callbacks run
+/// before any other task. Native builds have no event loop to yield to.
+#[cfg(target_arch = "wasm32")]
+pub async fn yield_to_event_loop() {
+    use wasm_bindgen::{JsCast, JsValue};
+
+    let global = js_sys::global();
+    let Some(set_timeout) = js_sys::Reflect::get(&global, &JsValue::from_str("setTimeout"))
+        .ok()
+        .and_then(|value| value.dyn_into::<js_sys::Function>().ok())
+    else {
+        return;
+    };
+    let tick = js_sys::Promise::new(&mut |resolve, _reject| {
+        let _ = set_timeout.call2(&global, &resolve, &JsValue::from(0));
+    });
+    let _ = wasm_bindgen_futures::JsFuture::from(tick).await;
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub async fn yield_to_event_loop() {}
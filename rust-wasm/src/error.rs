@@ -0,0 +1,165 @@
+use serde::Serialize;
+use thiserror::Error;
+use wasm_bindgen::JsValue;
+
+use crate::moves::Square;
+use crate::predictor::JsError;
+
+/// Recoverable failures raised by the board API when handed untrusted input (UI actions, stored
+/// snapshots). The hot MCTS loop only ever feeds legal actions and keeps using the panicking
+/// wrappers; everything crossing the JS boundary goes through the `try_*` variants instead.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum SantoriniError {
+    #[error("expected 75 entries for board state, got {len}")]
+    InvalidStateLength { len: usize },
+    #[error("player index must be 0 or 1, got {player}")]
+    InvalidPlayer { player: usize },
+    #[error("action {action} is outside the 162-entry action space")]
+    ActionOutOfRange { action: usize },
+    #[error("placement action {action} must be < 25")]
+    PlacementOutOfRange { action: usize },
+    #[error("player {player} attempted to place worker for player {expected}")]
+    WrongPlacementPlayer { player: usize, expected: usize },
+    #[error("cannot place worker on occupied square {square}")]
+    SquareOccupied { square: usize },
+    #[error("missing worker {worker} for player {player}")]
+    MissingWorker { worker: i8, player: usize },
+    #[error("action {action} moves or builds off the board")]
+    MoveOffBoard { action: usize },
+    #[error("action {action} is not legal for player {player}")]
+    IllegalMove { action: usize, player: usize },
+    #[error("no legal move from {from:?} to {to:?} building on {build:?}")]
+    IllegalCoordinates {
+        from: Square,
+        to: Option<Square>,
+        build: Option<Square>,
+    },
+    #[error("board violates {} invariant(s), first: {}", violations.len(), violations.first().map(ToString::to_string).unwrap_or_default())]
+    InvalidBoard { violations: Vec<BoardViolation> },
+}
+
+impl SantoriniError {
+    /// Stable, machine-readable identifier exposed to JavaScript as `error.code`.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::InvalidStateLength { .. } => "INVALID_STATE_LENGTH",
+            Self::InvalidPlayer { .. } => "INVALID_PLAYER",
+            Self::ActionOutOfRange { .. } => "ACTION_OUT_OF_RANGE",
+            Self::PlacementOutOfRange { .. } => "PLACEMENT_OUT_OF_RANGE",
+            Self::WrongPlacementPlayer { .. } => "WRONG_PLACEMENT_PLAYER",
+            Self::SquareOccupied { .. } => "SQUARE_OCCUPIED",
+            Self::MissingWorker { .. } => "MISSING_WORKER",
+            Self::MoveOffBoard { .. } => "MOVE_OFF_BOARD",
+            Self::IllegalMove { .. } => "ILLEGAL_MOVE",
+            Self::IllegalCoordinates { .. } => "ILLEGAL_COORDINATES",
+            Self::InvalidBoard { .. } => "INVALID_BOARD",
+        }
+    }
+}
+
+/// Why a search (or a move of its root) failed.
+#[derive(Debug, Error)]
+pub enum SearchError {
+    #[error(transparent)]
+    Board(#[from] SantoriniError),
+    #[error("evaluator failed: {0}")]
+    Evaluator(Box<dyn std::error::Error>),
+    #[error("evaluator returned {got} predictions for {expected} boards")]
+    MissingPredictions { expected: usize, got: usize },
+    #[error("predictor returned {len} policy entries, expected 162")]
+    ShortPolicy { len: usize },
+    #[error("no search root; call search or setRoot first")]
+    NoRoot,
+    #[error("root node missing after simulations")]
+    MissingRoot,
+}
+
+/// A single broken board invariant reported by [`crate::board::BoardState::violations`].
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum BoardViolation {
+    #[error("square {square} holds unknown worker id {value}")]
+    InvalidWorker { square: usize, value: i8 },
+    #[error("worker {worker} appears on both square {first} and square {second}")]
+    DuplicateWorker {
+        worker: i8,
+        first: usize,
+        second: usize,
+    },
+    #[error("square {square} has level {level}, expected 0..=4")]
+    InvalidLevel { square: usize, level: i8 },
+    #[error("worker {worker} stands on the dome at square {square}")]
+    WorkerOnDome { square: usize, worker: i8 },
+    #[error("player {player} placed a worker out of turn")]
+    PlacementOrder { player: usize },
+}
+
+impl BoardViolation {
+    /// Stable, machine-readable identifier mirroring [`SantoriniError::code`].
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::InvalidWorker { .. } => "INVALID_WORKER",
+            Self::DuplicateWorker { .. } => "DUPLICATE_WORKER",
+            Self::InvalidLevel { .. } => "INVALID_LEVEL",
+            Self::WorkerOnDome { .. } => "WORKER_ON_DOME",
+            Self::PlacementOrder { .. } => "PLACEMENT_ORDER",
+        }
+    }
+
+    /// Offending square, when the invariant is tied to a single cell.
+    pub fn square(&self) -> Option<usize> {
+        match self {
+            Self::InvalidWorker { square, .. }
+            | Self::InvalidLevel { square, .. }
+            | Self::WorkerOnDome { square, .. } => Some(*square),
+            Self::DuplicateWorker { second, .. } => Some(*second),
+            Self::PlacementOrder { .. } => None,
+        }
+    }
+
+    /// Plain `{ code, message, square }` record handed to JavaScript.
+    pub fn report(&self) -> ViolationReport {
+        ViolationReport {
+            code: self.code(),
+            message: self.to_string(),
+            square: self.square(),
+        }
+    }
+}
+
+/// Serialisable view of a [`BoardViolation`].
+#[derive(Debug, Serialize)]
+pub struct ViolationReport {
+    pub code: &'static str,
+    pub message: String,
+    pub square: Option<usize>,
+}
+
+impl From<SantoriniError> for JsValue {
+    /// Convert into a regular JS `Error` (catchable with `try`/`catch`) whose `name` is
+    /// `SantoriniError` and whose `code` property carries [`SantoriniError::code`].
+    fn from(err: SantoriniError) -> Self {
+        let js_error = js_sys::Error::new(&err.to_string());
+        js_error.set_name("SantoriniError");
+        let _ = js_sys::Reflect::set(
+            &js_error,
+            &JsValue::from_str("code"),
+            &JsValue::from_str(err.code()),
+        );
+        js_error.into()
+    }
+}
+
+impl From<SearchError> for JsValue {
+    /// Board errors become `SantoriniError`s, predictor failures are rethrown as they were
+    /// raised, and everything else is a plain message.
+    fn from(err: SearchError) -> Self {
+        match err {
+            SearchError::Board(err) => err.into(),
+            SearchError::Evaluator(err) => match err.downcast::<JsError>() {
+                Ok(js) => js.0,
+                Err(other) => JsValue::from_str(&other.to_string()),
+            },
+            other => JsValue::from_str(&other.to_string()),
+        }
+    }
+}
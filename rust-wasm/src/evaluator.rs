@@ -0,0 +1,44 @@
+//! Leaf evaluation interface of the search. An evaluator receives canonical boards (75 bytes
+//! each, the side to move is always player 0) with their legal-action masks (162 bytes of 0/1)
+//! and answers with one [`NetworkPrediction`] per board, like the JavaScript predictor does.
+
+use std::error::Error;
+use std::future::Future;
+
+use crate::board::{ACTION_SIZE, STATE_SIZE};
+use crate::predictor::NetworkPrediction;
+
+/// Evaluator answering on the calling thread, e.g. a heuristic or a model run in Rust.
+pub trait Evaluator {
+    type Error: Into<Box<dyn Error>>;
+
+    /// Score one board given its legal-action mask.
+    fn evaluate(&mut self, board: &[i8], valid: &[u8]) -> Result<NetworkPrediction, Self::Error>;
+
+    /// Score `count` boards stacked row by row. Defaults to one [`Evaluator::evaluate`] call per
+    /// board; implementations that vectorise should override it.
+    fn evaluate_batch(
+        &mut self,
+        boards: &[i8],
+        valid: &[u8],
+        count: usize,
+    ) -> Result<Vec<NetworkPrediction>, Self::Error> {
+        boards
+            .chunks_exact(STATE_SIZE)
+            .zip(valid.chunks_exact(ACTION_SIZE))
+            .take(count)
+            .map(|(board, valid)| self.evaluate(board, valid))
+            .collect()
+    }
+}
+
+/// Evaluator whose answers arrive later, e.g. a network behind a JavaScript Promise.
+pub trait AsyncEvaluator {
+    type Error: Into<Box<dyn Error>>;
+    /// Owns everything it needs: the search keeps working on its tree (or is stopped) while the
+    /// evaluation is pending, so it must not borrow the evaluator or the input slices.
+    type Pending: Future<Output = Result<Vec<NetworkPrediction>, Self::Error>> + 'static;
+
+    /// Start scoring `count` boards stacked row by row.
+    fn request(&mut self, boards: &[i8], valid: &[u8], count: usize) -> Self::Pending;
+}
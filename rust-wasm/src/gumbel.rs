@@ -0,0 +1,330 @@
+//! Gumbel AlphaZero root search (Danihelka et al., "Policy improvement by planning with Gumbel",
+//! ICLR 2022): Gumbel-Top-k sampling of root candidates, sequential halving to spread a small
+//! simulation budget over them, and the completed-Q improved policy returned to callers.
+
+use std::collections::VecDeque;
+
+use rand::Rng;
+
+use crate::board::ACTION_SIZE;
+
+/// Floor applied to priors before taking logarithms, so unlikely moves keep a finite logit.
+const MIN_PRIOR: f32 = 1e-12;
+
+/// Scale of the `σ(q)` transform: `(c_visit + max_b N(b)) · c_scale · q`.
+#[derive(Debug, Clone, Copy)]
+pub struct QTransform {
+    pub c_visit: f32,
+    pub c_scale: f32,
+}
+
+impl QTransform {
+    /// `σ(q)` for a value in `[-1, 1]`, rescaled to `[0, 1]` first.
+    fn sigma(&self, q: f32, max_visits: u32) -> f32 {
+        (self.c_visit + max_visits as f32) * self.c_scale * (q + 1.0) * 0.5
+    }
+}
+
+/// Root schedule for one search: which action each simulation starts with.
+pub struct SequentialHalving {
+    /// `(action, g(a) + logit(a))` for the sampled candidates; the first `active` entries are still
+    /// in the running.
+    candidates: Vec<(usize, f32)>,
+    active: usize,
+    queue: VecDeque<usize>,
+    started: bool,
+    phases_left: u32,
+    simulations_left: u32,
+    transform: QTransform,
+}
+
+impl SequentialHalving {
+    /// Sample up to `max_considered` candidates among the `valid` actions by Gumbel-Top-k over
+    /// `prior`, to be visited over roughly `simulations` simulations. Fewer are considered when
+    /// the budget cannot visit that many through every halving phase.
+    pub fn new(
+        rng: &mut impl Rng,
+        prior: &[f32; ACTION_SIZE],
+        valid: &[bool; ACTION_SIZE],
+        max_considered: usize,
+        simulations: u32,
+        transform: QTransform,
+    ) -> Self {
+        let mut candidates: Vec<(usize, f32)> = (0..ACTION_SIZE)
+            .filter(|&action| valid[action])
+            .map(|action| {
+                let uniform: f32 = rng.gen_range(f32::EPSILON..1.0);
+                let gumbel = -(-uniform.ln()).ln();
+                (action, gumbel + prior[action].max(MIN_PRIOR).ln())
+            })
+            .collect();
+        candidates.sort_by(|a, b| b.1.total_cmp(&a.1));
+        candidates.truncate(
+            max_considered
+                .min(affordable_candidates(simulations))
+                .max(1),
+        );
+        let active = candidates.len();
+        let phases_left = phases(active);
+        Self {
+            candidates,
+            active,
+            queue: VecDeque::new(),
+            started: false,
+            phases_left,
+            simulations_left: simulations,
+            transform,
+        }
+    }
+
+    /// Root action for the next simulation, halving the candidate set whenever a phase has been
+    /// spent. `None` when there is no valid action at all.
+    pub fn next_action(
+        &mut self,
+        nsa: &[u32; ACTION_SIZE],
+        qsa: &[f32; ACTION_SIZE],
+    ) -> Option<usize> {
+        if self.queue.is_empty() {
+            self.start_phase(nsa, qsa);
+        }
+        let action = self.queue.pop_front()?;
+        self.simulations_left = self.simulations_left.saturating_sub(1);
+        Some(action)
+    }
+
+    /// Withdraw the candidates `lost` reports as proven losses, so no more simulations go to
+    /// them and [`SequentialHalving::best`] never picks one.
+    pub fn retire(&mut self, lost: impl Fn(usize) -> bool) {
+        let mut kept = 0;
+        for slot in 0..self.active {
+            let candidate = self.candidates[slot];
+            if !lost(candidate.0) {
+                self.candidates.swap(kept, slot);
+                kept += 1;
+            }
+        }
+        self.active = kept;
+        self.queue.retain(|&action| !lost(action));
+    }
+
+    /// Hand back an action whose simulation did not happen after all.
+    pub fn requeue(&mut self, action: usize) {
+        self.queue.push_front(action);
+        self.simulations_left += 1;
+    }
+
+    /// The surviving candidate with the best `g + logit + σ(q)`, if any is left.
+    pub fn best(&self, nsa: &[u32; ACTION_SIZE], qsa: &[f32; ACTION_SIZE]) -> Option<usize> {
+        let max_visits = nsa.iter().copied().max().unwrap_or(0);
+        self.candidates[..self.active]
+            .iter()
+            .map(|&(action, score)| (action, self.score(action, score, nsa, qsa, max_visits)))
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(action, _)| action)
+    }
+
+    fn start_phase(&mut self, nsa: &[u32; ACTION_SIZE], qsa: &[f32; ACTION_SIZE]) {
+        if self.active == 0 {
+            return;
+        }
+        // Every phase but the first begins by dropping the weaker half.
+        if self.started {
+            let max_visits = nsa.iter().copied().max().unwrap_or(0);
+            let mut ranked: Vec<(usize, f32, f32)> = self.candidates[..self.active]
+                .iter()
+                .map(|&(action, score)| {
+                    (
+                        action,
+                        score,
+                        self.score(action, score, nsa, qsa, max_visits),
+                    )
+                })
+                .collect();
+            ranked.sort_by(|a, b| b.2.total_cmp(&a.2));
+            self.active = self.active.div_ceil(2).max(1);
+            for (slot, (action, score, _)) in ranked.into_iter().enumerate() {
+                self.candidates[slot] = (action, score);
+            }
+        }
+        self.started = true;
+        let per_action = (self.simulations_left / (self.phases_left * self.active as u32)).max(1);
+        self.phases_left = self.phases_left.saturating_sub(1).max(1);
+        for _ in 0..per_action {
+            self.queue.extend(
+                self.candidates[..self.active]
+                    .iter()
+                    .map(|&(action, _)| action),
+            );
+        }
+    }
+
+    fn score(
+        &self,
+        action: usize,
+        base: f32,
+        nsa: &[u32; ACTION_SIZE],
+        qsa: &[f32; ACTION_SIZE],
+        max_visits: u32,
+    ) -> f32 {
+        if nsa[action] == 0 {
+            return base;
+        }
+        base + self.transform.sigma(qsa[action], max_visits)
+    }
+}
+
+/// Number of halving phases for `candidates` candidates.
+fn phases(candidates: usize) -> u32 {
+    (candidates as f32).log2().ceil().max(1.0) as u32
+}
+
+/// Most candidates `simulations` can afford: `m` of them need `m · ⌈log2 m⌉` simulations for
+/// every phase to visit each survivor at least once.
+fn affordable_candidates(simulations: u32) -> usize {
+    (2..=ACTION_SIZE)
+        .take_while(|&count| count as u32 * phases(count) <= simulations)
+        .last()
+        .unwrap_or(1)
+}
+
+/// Completed-Q improved policy `softmax((logit + σ(completed Q)) / temperature)`, where unvisited
+/// actions take the mixed value estimate built from the node `value` and the visited actions' Q.
+/// `temperature` must be positive; at 1 this is the policy of the paper.
+pub fn improved_policy(
+    prior: &[f32; ACTION_SIZE],
+    valid: &[bool; ACTION_SIZE],
+    nsa: &[u32; ACTION_SIZE],
+    qsa: &[f32; ACTION_SIZE],
+    value: f32,
+    temperature: f32,
+    transform: QTransform,
+) -> Vec<f32> {
+    let total_visits: u32 = (0..ACTION_SIZE).filter(|&a| valid[a]).map(|a| nsa[a]).sum();
+    let max_visits = (0..ACTION_SIZE)
+        .filter(|&a| valid[a])
+        .map(|a| nsa[a])
+        .max()
+        .unwrap_or(0);
+    let (visited_prior, weighted_q) = (0..ACTION_SIZE)
+        .filter(|&a| valid[a] && nsa[a] > 0)
+        .fold((0.0f32, 0.0f32), |(mass, sum), a| {
+            (mass + prior[a], sum + prior[a] * qsa[a])
+        });
+    let mixed_value = if visited_prior > 0.0 {
+        (value + total_visits as f32 * weighted_q / visited_prior) / (1.0 + total_visits as f32)
+    } else {
+        value
+    };
+
+    let mut policy = vec![0.0f32; ACTION_SIZE];
+    let mut best = f32::NEG_INFINITY;
+    for action in (0..ACTION_SIZE).filter(|&a| valid[a]) {
+        let q = if nsa[action] > 0 {
+            qsa[action]
+        } else {
+            mixed_value
+        };
+        policy[action] =
+            (prior[action].max(MIN_PRIOR).ln() + transform.sigma(q, max_visits)) / temperature;
+        best = best.max(policy[action]);
+    }
+    let mut sum = 0.0;
+    for action in (0..ACTION_SIZE).filter(|&a| valid[a]) {
+        policy[action] = (policy[action] - best).exp();
+        sum += policy[action];
+    }
+    if sum > 0.0 {
+        for weight in &mut policy {
+            *weight /= sum;
+        }
+    }
+    policy
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::SmallRng;
+    use rand::SeedableRng;
+
+    const TRANSFORM: QTransform = QTransform {
+        c_visit: 50.0,
+        c_scale: 1.0,
+    };
+
+    #[test]
+    fn sequential_halving_narrows_onto_the_best_candidate() {
+        let mut prior = [0.0; ACTION_SIZE];
+        let mut valid = [false; ACTION_SIZE];
+        let good = [10, 20, 30, 40, 50, 60, 70, 80];
+        for &action in &good {
+            valid[action] = true;
+            prior[action] = 1.0 / good.len() as f32;
+        }
+        let mut rng = SmallRng::seed_from_u64(3);
+        let mut halving = SequentialHalving::new(&mut rng, &prior, &valid, 8, 24, TRANSFORM);
+
+        // Action 50 is the only one that pays off, whichever candidates were drawn.
+        let mut nsa = [0u32; ACTION_SIZE];
+        let mut qsa = [0.0f32; ACTION_SIZE];
+        let mut seen = Vec::new();
+        for _ in 0..24 {
+            let action = halving.next_action(&nsa, &qsa).expect("candidates");
+            let value = if action == 50 { 0.9 } else { -0.5 };
+            nsa[action] += 1;
+            qsa[action] += (value - qsa[action]) / nsa[action] as f32;
+            if !seen.contains(&action) {
+                seen.push(action);
+            }
+        }
+        // 8 candidates, then 4, then 2, each phase spending 8 simulations.
+        assert_eq!(seen.len(), 8);
+        assert_eq!(nsa[50], 1 + 2 + 4);
+        assert_eq!(halving.best(&nsa, &qsa), Some(50));
+    }
+
+    #[test]
+    fn small_budgets_consider_fewer_candidates_and_skip_lost_ones() {
+        let prior = [1.0 / ACTION_SIZE as f32; ACTION_SIZE];
+        let valid = [true; ACTION_SIZE];
+        let mut rng = SmallRng::seed_from_u64(5);
+        // Eight simulations afford four candidates over two phases, not the sixteen asked for.
+        let mut halving = SequentialHalving::new(&mut rng, &prior, &valid, 16, 8, TRANSFORM);
+        let nsa = [0u32; ACTION_SIZE];
+        let qsa = [0.0f32; ACTION_SIZE];
+        let first = halving.next_action(&nsa, &qsa).expect("candidates");
+        halving.requeue(first);
+        assert_eq!(halving.active, 4);
+
+        halving.retire(|action| action == first);
+        assert_eq!(halving.active, 3);
+        assert!((0..8).all(|_| halving.next_action(&nsa, &qsa) != Some(first)));
+        assert_ne!(halving.best(&nsa, &qsa), Some(first));
+    }
+
+    #[test]
+    fn improved_policy_shifts_mass_towards_better_q() {
+        let mut prior = [0.0; ACTION_SIZE];
+        let mut valid = [false; ACTION_SIZE];
+        for action in [1, 2, 3] {
+            valid[action] = true;
+            prior[action] = 1.0 / 3.0;
+        }
+        let mut nsa = [0u32; ACTION_SIZE];
+        let mut qsa = [0.0f32; ACTION_SIZE];
+        nsa[1] = 4;
+        qsa[1] = 0.8;
+        nsa[2] = 4;
+        qsa[2] = -0.8;
+
+        let policy = improved_policy(&prior, &valid, &nsa, &qsa, 0.0, 1.0, TRANSFORM);
+        assert!((policy.iter().sum::<f32>() - 1.0).abs() < 1e-5);
+        assert!(policy[1] > policy[3] && policy[3] > policy[2]);
+        assert_eq!(policy[0], 0.0);
+
+        // Higher temperatures flatten the same preference.
+        let flat = improved_policy(&prior, &valid, &nsa, &qsa, 0.0, 100.0, TRANSFORM);
+        assert!(flat[1] < policy[1] && flat[2] > policy[2]);
+        assert!(flat[1] > flat[3] && flat[3] > flat[2]);
+    }
+}
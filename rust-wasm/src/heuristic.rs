@@ -0,0 +1,329 @@
+//! Hand-written evaluator so the search can play without the neural network. Values weigh
+//! height, mobility and climb threats (an opponent threat counts for less when it can be domed
+//! this turn) and are squashed with `tanh`. The policy is either flat over the legal moves or
+//! shaped towards climbing, doming open threats and away from building the opponent a step.
+
+use crate::board::{BoardState, BOARD_SIZE, CELL_COUNT, CHANNELS};
+use crate::error::{BoardViolation, SantoriniError};
+use crate::evaluator::Evaluator;
+use crate::moves::{Move, Square};
+use crate::predictor::NetworkPrediction;
+use crate::tables::NEIGHBOURS;
+
+/// Value of one level of height under a worker; the other weights are relative to it.
+const HEIGHT_WEIGHT: f32 = 1.0;
+/// Per destination square a worker could step to.
+const MOBILITY_WEIGHT: f32 = 0.1;
+/// Per square the side to move could win on right away.
+const THREAT_WEIGHT: f32 = 2.0;
+/// Per opponent winning square left open, and for the single one that can still be domed.
+const OPEN_THREAT_WEIGHT: f32 = 2.5;
+const BLOCKABLE_THREAT_WEIGHT: f32 = 0.75;
+/// Maps the weighted sum onto `tanh`'s responsive range.
+const VALUE_SCALE: f32 = 0.3;
+
+/// Policy logits: per level climbed and per level stood on after the move.
+const CLIMB_LOGIT: f32 = 1.0;
+const HEIGHT_LOGIT: f32 = 0.5;
+/// Doming a square the opponent threatens to win on.
+const DOME_BLOCK_LOGIT: f32 = 3.0;
+/// Raising a square to level 3 next to one of our own workers on level 2.
+const SETUP_LOGIT: f32 = 1.0;
+/// Raising a square to level 3 next to an opponent worker on level 2.
+const GIFT_LOGIT: f32 = -3.0;
+/// Placements, per ring closer to the centre.
+const CENTRE_LOGIT: f32 = 0.5;
+
+/// Leaf evaluator built from Santorini rules of thumb rather than a trained network.
+#[derive(Debug, Clone, Copy)]
+pub struct HeuristicEvaluator {
+    /// Shape the policy with move heuristics; otherwise every legal move gets the same prior.
+    pub shaped_policy: bool,
+}
+
+impl Default for HeuristicEvaluator {
+    fn default() -> Self {
+        Self {
+            shaped_policy: true,
+        }
+    }
+}
+
+impl Evaluator for HeuristicEvaluator {
+    type Error = SantoriniError;
+
+    /// Fails on boards of the wrong length and on unknown worker ids or levels outside `0..=4`,
+    /// which the features cannot be read from.
+    fn evaluate(
+        &mut self,
+        board: &[i8],
+        valid: &[u8],
+    ) -> Result<NetworkPrediction, SantoriniError> {
+        let state = BoardState::try_from_bytes(board)?;
+        let unreadable: Vec<BoardViolation> = state
+            .violations()
+            .into_iter()
+            .filter(|violation| {
+                matches!(
+                    violation,
+                    BoardViolation::InvalidWorker { .. } | BoardViolation::InvalidLevel { .. }
+                )
+            })
+            .collect();
+        if !unreadable.is_empty() {
+            return Err(SantoriniError::InvalidBoard {
+                violations: unreadable,
+            });
+        }
+
+        let position = Position::read(board);
+        let mut pi = vec![0.0; valid.len()];
+        if self.shaped_policy {
+            for mv in state.legal_moves(0) {
+                if let Some(slot) = pi.get_mut(mv.action()) {
+                    *slot = position.move_logit(&mv);
+                }
+            }
+        }
+        Ok(NetworkPrediction {
+            pi,
+            v: (VALUE_SCALE * position.score()).tanh(),
+        })
+    }
+}
+
+/// Worker and level channels of a canonical board; player 0 (workers `1` and `2`) is to move.
+struct Position {
+    workers: [i8; CELL_COUNT],
+    levels: [i8; CELL_COUNT],
+}
+
+impl Position {
+    fn read(board: &[i8]) -> Self {
+        let mut workers = [0; CELL_COUNT];
+        let mut levels = [0; CELL_COUNT];
+        for (square, cell) in board.chunks_exact(CHANNELS).take(CELL_COUNT).enumerate() {
+            workers[square] = cell[0];
+            levels[square] = cell[1];
+        }
+        Self { workers, levels }
+    }
+
+    /// Weighted features from the side to move's point of view.
+    fn score(&self) -> f32 {
+        let (our_height, our_mobility) = self.height_and_mobility(true);
+        let (their_height, their_mobility) = self.height_and_mobility(false);
+        let ours = self.threats(true).count_ones() as f32;
+
+        let theirs = self.threats(false);
+        let open = theirs.count_ones() as f32;
+        let threat_penalty = if theirs == 0 {
+            0.0
+        } else if self.can_dome_any(theirs) {
+            // One dome per turn: a single threat is answered, any further one stays open.
+            BLOCKABLE_THREAT_WEIGHT + OPEN_THREAT_WEIGHT * (open - 1.0)
+        } else {
+            OPEN_THREAT_WEIGHT * open
+        };
+
+        HEIGHT_WEIGHT * (our_height - their_height) as f32
+            + MOBILITY_WEIGHT * (our_mobility - their_mobility) as f32
+            + THREAT_WEIGHT * ours
+            - threat_penalty
+    }
+
+    fn move_logit(&self, mv: &Move) -> f32 {
+        match *mv {
+            Move::Placement { square, .. } => {
+                let ring = square.0.abs_diff(2).max(square.1.abs_diff(2));
+                CENTRE_LOGIT * (2 - ring) as f32
+            }
+            Move::Step {
+                from, to, build, ..
+            } => {
+                let (from, to, build) = (index(from), index(to), index(build));
+                let climbed = self.levels[to] - self.levels[from];
+                let mut logit =
+                    CLIMB_LOGIT * climbed as f32 + HEIGHT_LOGIT * self.levels[to] as f32;
+                match self.levels[build] {
+                    3 if self.threats(false) & (1 << build) != 0 => logit += DOME_BLOCK_LOGIT,
+                    2 => {
+                        if self.levels[to] == 2 && NEIGHBOURS[to] & (1 << build) != 0 {
+                            logit += SETUP_LOGIT;
+                        }
+                        if self.workers_on_level(false, 2) & NEIGHBOURS[build] != 0 {
+                            logit += GIFT_LOGIT;
+                        }
+                    }
+                    _ => {}
+                }
+                logit
+            }
+        }
+    }
+
+    /// Summed worker heights and destination counts of one side.
+    fn height_and_mobility(&self, ours: bool) -> (i32, i32) {
+        (0..CELL_COUNT)
+            .filter(|&square| self.belongs(square, ours))
+            .fold((0, 0), |(height, mobility), square| {
+                (
+                    height + i32::from(self.levels[square]),
+                    mobility + self.destinations(square).count_ones() as i32,
+                )
+            })
+    }
+
+    /// Level-3 squares one side could step onto from level 2, i.e. win on.
+    fn threats(&self, ours: bool) -> u32 {
+        let mut squares = 0;
+        let mut mask = self.workers_on_level(ours, 2);
+        while mask != 0 {
+            let square = mask.trailing_zeros() as usize;
+            squares |= self.destinations(square) & self.level_mask(3);
+            mask &= mask - 1;
+        }
+        squares
+    }
+
+    /// Whether the side to move can step somewhere next to one of `targets` and dome it.
+    fn can_dome_any(&self, targets: u32) -> bool {
+        (0..CELL_COUNT)
+            .filter(|&square| self.belongs(square, true))
+            .any(|square| {
+                let mut reach = self.destinations(square);
+                while reach != 0 {
+                    let to = reach.trailing_zeros() as usize;
+                    if NEIGHBOURS[to] & targets != 0 {
+                        return true;
+                    }
+                    reach &= reach - 1;
+                }
+                false
+            })
+    }
+
+    fn destinations(&self, from: usize) -> u32 {
+        let mut mask = 0;
+        let mut neighbours = NEIGHBOURS[from];
+        while neighbours != 0 {
+            let to = neighbours.trailing_zeros() as usize;
+            if self.workers[to] == 0
+                && self.levels[to] < 4
+                && self.levels[to] <= self.levels[from] + 1
+            {
+                mask |= 1 << to;
+            }
+            neighbours &= neighbours - 1;
+        }
+        mask
+    }
+
+    fn workers_on_level(&self, ours: bool, level: i8) -> u32 {
+        (0..CELL_COUNT)
+            .filter(|&square| self.belongs(square, ours) && self.levels[square] == level)
+            .fold(0, |mask, square| mask | 1 << square)
+    }
+
+    fn level_mask(&self, level: i8) -> u32 {
+        (0..CELL_COUNT)
+            .filter(|&square| self.levels[square] == level)
+            .fold(0, |mask, square| mask | 1 << square)
+    }
+
+    fn belongs(&self, square: usize, ours: bool) -> bool {
+        let worker = self.workers[square];
+        if ours {
+            worker > 0
+        } else {
+            worker < 0
+        }
+    }
+}
+
+fn index((y, x): Square) -> usize {
+    y * BOARD_SIZE + x
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::{ACTION_SIZE, STATE_SIZE};
+
+    fn position(workers: &[(usize, i8)], levels: &[(usize, i8)]) -> [i8; STATE_SIZE] {
+        let mut bytes = [0i8; STATE_SIZE];
+        for &(square, worker) in workers {
+            bytes[square * 3] = worker;
+        }
+        for &(square, level) in levels {
+            bytes[square * 3 + 1] = level;
+        }
+        bytes
+    }
+
+    fn evaluate(board: &[i8]) -> NetworkPrediction {
+        let state = BoardState::from_bytes(board);
+        let mut valid = [false; ACTION_SIZE];
+        state.valid_moves(0, &mut valid);
+        let mask: Vec<u8> = valid.iter().map(|&flag| u8::from(flag)).collect();
+        HeuristicEvaluator::default()
+            .evaluate(board, &mask)
+            .expect("valid board")
+    }
+
+    #[test]
+    fn value_rewards_height_and_punishes_open_threats() {
+        let workers = [(6, 1), (8, 2), (16, -1), (18, -2)];
+        let even = evaluate(&position(&workers, &[]));
+        assert!(even.v.abs() < 1e-6);
+
+        let higher = evaluate(&position(&workers, &[(6, 1)]));
+        assert!(higher.v > 0.0);
+
+        // Worker -1 stands on level 2 next to a level-3 square nobody of ours can reach.
+        let threatened = evaluate(&position(&workers, &[(16, 2), (21, 3)]));
+        assert!(threatened.v < -0.5);
+        assert!((-1.0..=1.0).contains(&threatened.v));
+    }
+
+    #[test]
+    fn shaped_policy_domes_the_opponents_winning_square() {
+        // Worker -1 on level 2 threatens the level-3 square 12; worker 1 can dome it.
+        let board = position(&[(6, 1), (0, 2), (17, -1), (24, -2)], &[(17, 2), (12, 3)]);
+        let prediction = evaluate(&board);
+        let best = BoardState::from_bytes(&board)
+            .legal_moves(0)
+            .max_by(|a, b| prediction.pi[a.action()].total_cmp(&prediction.pi[b.action()]))
+            .expect("legal moves");
+        let Move::Step { build, .. } = best else {
+            panic!("expected a regular move, got {best:?}");
+        };
+        assert_eq!(index(build), 12);
+    }
+
+    #[test]
+    fn unreadable_boards_are_rejected() {
+        let mut heuristic = HeuristicEvaluator::default();
+        let mask = [1u8; ACTION_SIZE];
+        assert_eq!(
+            heuristic.evaluate(&[0; 10], &mask).err(),
+            Some(SantoriniError::InvalidStateLength { len: 10 })
+        );
+        let board = position(&[(6, 1), (8, 3)], &[(12, 127)]);
+        assert_eq!(
+            heuristic.evaluate(&board, &mask).err(),
+            Some(SantoriniError::InvalidBoard {
+                violations: vec![
+                    BoardViolation::InvalidWorker {
+                        square: 8,
+                        value: 3
+                    },
+                    BoardViolation::InvalidLevel {
+                        square: 12,
+                        level: 127
+                    },
+                ]
+            })
+        );
+    }
+}
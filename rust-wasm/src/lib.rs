@@ -10,17 +10,43 @@
 //! * [`SantoriniMcts`] – a batched Monte Carlo Tree Search orchestrator that relies on an
 //!   externally supplied neural-network evaluator (JavaScript/TypeScript side). The evaluator is
 //!   expected to return a Promise resolving to `{ pi: number[], v: number }`, matching the output
-//!   of the Pyodide version. The implementation focuses on clarity, documentation and predictable
-//!   performance.
+//!   of the Pyodide version; without one it falls back to a built-in [`HeuristicEvaluator`].
+//!   With `batch_size > 1` leaves are gathered under virtual loss and evaluated together,
+//!   through `setBatchPredictor` when a stacked-input evaluator is available.
+//!   The search itself is the generic [`Mcts`], which takes any [`Evaluator`] or
+//!   [`AsyncEvaluator`] and runs natively as well, e.g. under `cargo test`. The `onnx` feature
+//!   adds `OnnxEvaluator`, which runs `model_no_god.onnx` in Rust (`SantoriniMcts.withOnnxModel`).
+//!   The implementation focuses on clarity, documentation and predictable performance.
 //!
 //! Both components are heavily documented to ease maintenance and future optimisation passes.
 
 mod board;
+mod clock;
+mod error;
+mod evaluator;
+mod gumbel;
+mod heuristic;
 mod mcts;
+mod moves;
+#[cfg(feature = "onnx")]
+mod onnx;
+mod perft;
 mod predictor;
+mod tables;
+mod zobrist;
 
 pub use board::{SantoriniBoard, ACTION_SIZE, STATE_SIZE};
-pub use mcts::{MctsConfig, SantoriniMcts, SEARCH_RESULT_VERSION};
+pub use error::{BoardViolation, SantoriniError, SearchError};
+pub use evaluator::{AsyncEvaluator, Evaluator};
+pub use heuristic::HeuristicEvaluator;
+pub use mcts::{
+    EdgeStats, Mcts, MctsConfig, Proof, PvStep, RootPolicy, SantoriniMcts, SearchAbort,
+    SearchResult, TranspositionStats, SEARCH_RESULT_VERSION,
+};
+pub use moves::{Move, Square};
+#[cfg(feature = "onnx")]
+pub use onnx::OnnxEvaluator;
+pub use predictor::NetworkPrediction;
 
 use wasm_bindgen::prelude::*;
 
@@ -1,4 +1,7 @@
-use std::collections::HashMap;
+use std::cell::{Cell, RefCell};
+use std::future::{poll_fn, Future};
+use std::pin::{pin, Pin};
+use std::rc::Rc;
 
 use rand::distributions::Distribution;
 use rand::rngs::SmallRng;
@@ -6,16 +9,29 @@ use rand::{Rng, SeedableRng};
 use rand_distr::Dirichlet;
 use serde::{Deserialize, Serialize};
 use wasm_bindgen::prelude::*;
-use wasm_bindgen_futures::JsFuture;
+
+use hashbrown::{HashMap, HashSet};
 
 use crate::board::{BoardState, ACTION_SIZE, STATE_SIZE};
-use crate::predictor::NetworkPrediction;
+use crate::clock;
+use crate::error::{SantoriniError, SearchError};
+use crate::evaluator::{AsyncEvaluator, Evaluator};
+use crate::gumbel::{improved_policy, QTransform, SequentialHalving};
+use crate::heuristic::HeuristicEvaluator;
+#[cfg(feature = "onnx")]
+use crate::onnx::OnnxEvaluator;
+use crate::predictor::{JsPredictor, NetworkPrediction};
+use crate::zobrist::BuildZobristHasher;
 
 const MIN_FLOAT: f32 = f32::MIN;
 const EPS: f32 = 1e-8;
 
+/// Longest a run keeps the JavaScript event loop busy between yields when nothing else hands it
+/// back, i.e. while pondering or while the evaluator answers on the spot.
+const YIELD_SLICE_MS: f64 = 20.0;
+
 /// Version tag embedded in search results so the frontend can gate feature toggles if needed.
-pub const SEARCH_RESULT_VERSION: u8 = 1;
+pub const SEARCH_RESULT_VERSION: u8 = 8;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MctsConfig {
@@ -46,15 +62,55 @@ pub struct MctsConfig {
     /// Coefficient `k` used in the forced-playout threshold `sqrt(k * P * n_iter)`.
     #[serde(default = "default_forced_playout_coefficient")]
     pub forced_playout_coefficient: f32,
-    /// When true, skip periodic cleanup of the search tree (higher memory usage).
+    /// When true, keep every node when the root moves instead of pruning the tree down to the
+    /// subtree under the new root (higher memory usage).
     #[serde(default)]
     pub no_mem_optim: bool,
-    /// Interval (in rounds) between transposition-table cleanups.
-    #[serde(default = "default_cleanup_interval")]
-    pub cleanup_interval: u16,
-    /// Number of recent rounds to retain in the tree during cleanup.
-    #[serde(default = "default_retain_rounds")]
-    pub retain_rounds: u16,
+    /// Number of leaves gathered (under virtual loss) before the predictor is called. `1` keeps
+    /// the strictly sequential search.
+    #[serde(default = "default_batch_size")]
+    pub batch_size: u32,
+    /// Value assumed for each pending visit of an edge whose leaf is still awaiting evaluation.
+    #[serde(default = "default_virtual_loss")]
+    pub virtual_loss: f32,
+    /// Number of principal variations reported, one per most-visited root move.
+    #[serde(default = "default_multi_pv")]
+    pub multi_pv: u32,
+    /// Per-ply discount on proven results: a win (or loss) `n` plies away backs up as
+    /// `±(1 - terminal_discount)^n`, so quicker wins and slower losses score better. 0 disables it.
+    #[serde(default)]
+    pub terminal_discount: f32,
+    /// How root moves are explored and picked.
+    #[serde(default)]
+    pub root_policy: RootPolicy,
+    /// Gumbel root only: most root moves sampled for sequential halving. Small budgets sample
+    /// fewer, so that every halving phase still visits each remaining move.
+    #[serde(default = "default_gumbel_max_considered")]
+    pub gumbel_max_considered: u32,
+    /// Gumbel root only: `c_visit` in the `σ(q)` value transform.
+    #[serde(default = "default_gumbel_c_visit")]
+    pub gumbel_c_visit: f32,
+    /// Gumbel root only: `c_scale` in the `σ(q)` value transform.
+    #[serde(default = "default_gumbel_c_scale")]
+    pub gumbel_c_scale: f32,
+    /// Stop as soon as the most-visited root move can no longer be overtaken within the
+    /// simulation budget, and skip searching positions with a single legal move (beyond
+    /// evaluating the root once, so the result still carries a value).
+    #[serde(default)]
+    pub early_stop: bool,
+}
+
+/// Root move selection strategy.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RootPolicy {
+    /// PUCT at the root like everywhere else; the policy follows visit counts.
+    #[default]
+    Puct,
+    /// Gumbel-Top-k candidates visited by sequential halving; the policy is the completed-Q
+    /// improved policy, sharpened or flattened by the search temperature (at 0, the halving
+    /// winner). Holds up far better with only a handful of simulations.
+    Gumbel,
 }
 
 fn default_partial_divisor() -> u32 {
@@ -81,11 +137,23 @@ fn default_forced_playouts() -> bool {
 fn default_forced_playout_coefficient() -> f32 {
     0.5
 }
-fn default_cleanup_interval() -> u16 {
-    20
+fn default_batch_size() -> u32 {
+    1
+}
+fn default_virtual_loss() -> f32 {
+    1.0
+}
+fn default_multi_pv() -> u32 {
+    1
+}
+fn default_gumbel_max_considered() -> u32 {
+    16
+}
+fn default_gumbel_c_visit() -> f32 {
+    50.0
 }
-fn default_retain_rounds() -> u16 {
-    5
+fn default_gumbel_c_scale() -> f32 {
+    1.0
 }
 
 impl Default for MctsConfig {
@@ -101,8 +169,15 @@ impl Default for MctsConfig {
             forced_playouts: default_forced_playouts(),
             forced_playout_coefficient: default_forced_playout_coefficient(),
             no_mem_optim: false,
-            cleanup_interval: default_cleanup_interval(),
-            retain_rounds: default_retain_rounds(),
+            batch_size: default_batch_size(),
+            virtual_loss: default_virtual_loss(),
+            multi_pv: default_multi_pv(),
+            terminal_discount: 0.0,
+            root_policy: RootPolicy::default(),
+            gumbel_max_considered: default_gumbel_max_considered(),
+            gumbel_c_visit: default_gumbel_c_visit(),
+            gumbel_c_scale: default_gumbel_c_scale(),
+            early_stop: false,
         }
     }
 }
@@ -115,7 +190,52 @@ struct TreeNode {
     nsa: [u32; ACTION_SIZE],
     mean_value: f32,
     terminal_value: Option<f32>,
+    /// Latest round at which the search reached this position. Rounds only grow along a game, so
+    /// nodes last seen before the root's round cannot lie under it.
     round: u16,
+    solver: Solver,
+}
+
+/// Game-theoretic result of a position, from the perspective of the player to move there.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Proof {
+    pub won: bool,
+    /// Plies until the game ends under best play: the quickest win or the longest loss.
+    pub plies: u16,
+    /// Move achieving `plies`; `None` on finished games.
+    pub action: Option<usize>,
+}
+
+impl Proof {
+    fn value(&self) -> f32 {
+        if self.won {
+            1.0
+        } else {
+            -1.0
+        }
+    }
+
+    fn flipped(self) -> Self {
+        Self {
+            won: !self.won,
+            ..self
+        }
+    }
+}
+
+/// MCTS-solver bookkeeping: the node's proof once known, plus the edges already proven to lose.
+#[derive(Debug, Clone, Copy, Default)]
+struct Solver {
+    proof: Option<Proof>,
+    lost_edges: [u64; 3],
+    /// Longest of the proven losses so far, with the edge leading to it.
+    longest_loss: Option<(u16, usize)>,
+}
+
+impl Solver {
+    fn is_lost(&self, action: usize) -> bool {
+        self.lost_edges[action / 64] & (1 << (action % 64)) != 0
+    }
 }
 
 impl TreeNode {
@@ -140,9 +260,7 @@ impl TreeNode {
         if sum <= EPS {
             if valid_count == 0 {
                 let uniform = 1.0 / ACTION_SIZE as f32;
-                for weight in &mut policy {
-                    *weight = uniform;
-                }
+                policy.fill(uniform);
             } else {
                 let uniform = 1.0 / valid_count as f32;
                 for (idx, flag) in valid.iter().copied().enumerate() {
@@ -172,20 +290,91 @@ impl TreeNode {
             mean_value: prediction.v,
             terminal_value: None,
             round,
+            solver: Solver::default(),
         }
     }
 
     fn terminal(valid: [bool; ACTION_SIZE], value: f32, round: u16) -> Self {
+        let proof = Proof {
+            won: value > 0.0,
+            plies: 0,
+            action: None,
+        };
+        Self {
+            mean_value: value,
+            terminal_value: Some(value),
+            ..Self::solved(valid, proof, round)
+        }
+    }
+
+    /// Node whose result is known without evaluating it, e.g. one with a winning move.
+    fn solved(valid: [bool; ACTION_SIZE], proof: Proof, round: u16) -> Self {
         Self {
             policy: [0.0; ACTION_SIZE],
             valid,
             visit_count: 0,
             qsa: [0.0; ACTION_SIZE],
             nsa: [0; ACTION_SIZE],
-            mean_value: value,
-            terminal_value: Some(value),
+            mean_value: proof.value(),
+            terminal_value: None,
             round,
+            solver: Solver {
+                proof: Some(proof),
+                ..Solver::default()
+            },
+        }
+    }
+
+    /// Whether more search below this node is pointless. A proven win can still be shortened,
+    /// unless it is immediate.
+    fn settled(&self) -> bool {
+        self.solver
+            .proof
+            .is_some_and(|proof| !proof.won || proof.plies <= 1)
+    }
+
+    /// Fold in the proven result of the child behind `action`, already turned to this node's
+    /// side. Returns this node's own proof once it is known: one winning edge proves a win (and
+    /// a quicker one replaces it), while a loss needs every valid edge to be proven lost.
+    fn absorb_proof(&mut self, action: usize, child: Proof) -> Option<Proof> {
+        let plies = child.plies.saturating_add(1);
+        if let Some(proof) = self.solver.proof {
+            if proof.won && child.won && plies < proof.plies {
+                self.solver.proof = Some(Proof {
+                    won: true,
+                    plies,
+                    action: Some(action),
+                });
+            }
+            return self.solver.proof;
+        }
+        if child.won {
+            self.solver.proof = Some(Proof {
+                won: true,
+                plies,
+                action: Some(action),
+            });
+            return self.solver.proof;
+        }
+        if !self.solver.is_lost(action) {
+            self.solver.lost_edges[action / 64] |= 1 << (action % 64);
+            if self
+                .solver
+                .longest_loss
+                .is_none_or(|(longest, _)| plies > longest)
+            {
+                self.solver.longest_loss = Some((plies, action));
+            }
+        }
+        let all_lost = (0..ACTION_SIZE).all(|edge| !self.valid[edge] || self.solver.is_lost(edge));
+        if let (true, Some((plies, action))) = (all_lost, self.solver.longest_loss) {
+            self.solver.proof = Some(Proof {
+                won: false,
+                plies,
+                action: Some(action),
+            });
         }
+        self.solver.proof
     }
 
     fn select_action(
@@ -195,38 +384,33 @@ impl TreeNode {
         forced_playouts: bool,
         iteration: u32,
         coefficient: f32,
+        in_flight: Option<&InFlight>,
+        virtual_loss: f32,
     ) -> usize {
-        let sqrt_ns = (self.visit_count as f32 + EPS).sqrt();
-        let total = (self.visit_count as f32).sqrt();
-        let base_fpu = self.mean_value - fpu;
+        let puct = self.puct(
+            cpuct,
+            fpu,
+            in_flight.map_or(0, |pending| pending.node),
+            virtual_loss,
+        );
         let mut best = MIN_FLOAT;
         let mut best_action = 0;
         let iter_f = iteration.max(1) as f32;
         for (action, valid_flag) in self.valid.iter().copied().enumerate() {
-            if !valid_flag {
+            if !valid_flag || self.solver.is_lost(action) {
                 continue;
             }
+            let pending = in_flight.map_or(0, |pending| pending.edges[action]);
+            let visits = self.nsa[action] + pending;
             if forced_playouts {
                 let expected = (coefficient * self.policy[action].max(0.0) * iter_f)
                     .sqrt()
                     .floor() as u32;
-                if self.nsa[action] < expected {
+                if visits < expected {
                     return action;
                 }
             }
-            let visits = self.nsa[action];
-            let (q, exploration) = if visits == 0 {
-                let exploration = cpuct * self.policy[action] * sqrt_ns;
-                (base_fpu, exploration)
-            } else {
-                let exploration = if total > 0.0 {
-                    cpuct * self.policy[action] * total / (1.0 + visits as f32)
-                } else {
-                    0.0
-                };
-                (self.qsa[action], exploration)
-            };
-            let u = q + exploration;
+            let u = self.edge_score(action, pending, &puct);
             if u > best {
                 best = u;
                 best_action = action;
@@ -235,6 +419,82 @@ impl TreeNode {
         best_action
     }
 
+    /// Node-level terms of the selection score, with `pending` in-flight visits added.
+    fn puct(&self, cpuct: f32, fpu: f32, pending: u32, virtual_loss: f32) -> Puct {
+        let visit_count = self.visit_count + pending;
+        Puct {
+            cpuct,
+            sqrt_ns: (visit_count as f32 + EPS).sqrt(),
+            total: (visit_count as f32).sqrt(),
+            base_fpu: self.mean_value - fpu,
+            virtual_loss,
+        }
+    }
+
+    /// Selection score `Q + U` of `action`, counting `pending` in-flight visits as losses.
+    #[inline]
+    fn edge_score(&self, action: usize, pending: u32, puct: &Puct) -> f32 {
+        let visits = self.nsa[action] + pending;
+        let (q, exploration) = if visits == 0 {
+            let exploration = puct.cpuct * self.policy[action] * puct.sqrt_ns;
+            (puct.base_fpu, exploration)
+        } else {
+            let exploration = if puct.total > 0.0 {
+                puct.cpuct * self.policy[action] * puct.total / (1.0 + visits as f32)
+            } else {
+                0.0
+            };
+            let q = if pending == 0 {
+                self.qsa[action]
+            } else {
+                (self.qsa[action] * self.nsa[action] as f32 - puct.virtual_loss * pending as f32)
+                    / visits as f32
+            };
+            (q, exploration)
+        };
+        q + exploration
+    }
+
+    /// Visits by which the most-visited valid edge leads the runner-up.
+    fn leader_margin(&self) -> u32 {
+        let (mut first, mut second) = (0, 0);
+        for (&visits, _) in self
+            .nsa
+            .iter()
+            .zip(self.valid.iter())
+            .filter(|(_, &flag)| flag)
+        {
+            if visits > first {
+                second = first;
+                first = visits;
+            } else if visits > second {
+                second = visits;
+            }
+        }
+        first - second
+    }
+
+    /// Per-edge statistics of every valid action, sorted by visits and then by prior.
+    fn edge_stats(&self, cpuct: f32, fpu: f32) -> Vec<EdgeStats> {
+        let puct = self.puct(cpuct, fpu, 0, 0.0);
+        let mut edges: Vec<EdgeStats> = (0..ACTION_SIZE)
+            .filter(|&action| self.valid[action])
+            .map(|action| EdgeStats {
+                action,
+                prior: self.policy[action],
+                q: (self.nsa[action] > 0).then_some(self.qsa[action]),
+                visits: self.nsa[action],
+                ucb: self.edge_score(action, 0, &puct),
+            })
+            .collect();
+        edges.sort_by(|a, b| {
+            b.visits
+                .cmp(&a.visits)
+                .then_with(|| b.prior.total_cmp(&a.prior))
+        });
+        edges
+    }
+
     fn record_value(&mut self, value: f32) {
         let previous_visits = self.visit_count;
         let weight = (previous_visits + 1) as f32;
@@ -278,74 +538,544 @@ impl TreeNode {
     }
 }
 
+/// Node-level terms of the PUCT score, computed once per selection.
+struct Puct {
+    cpuct: f32,
+    sqrt_ns: f32,
+    total: f32,
+    base_fpu: f32,
+    virtual_loss: f32,
+}
+
+/// Visits handed out to leaves that are still awaiting their network evaluation.
+struct InFlight {
+    node: u32,
+    edges: [u32; ACTION_SIZE],
+}
+
+/// Unexpanded position reached by one descent, together with the path that led to it.
+struct PendingLeaf {
+    key: u64,
+    board: BoardState,
+    valid: [bool; ACTION_SIZE],
+    path: Vec<(u64, usize, bool)>,
+}
+
+impl PendingLeaf {
+    /// The part of the path from the node keyed `root` down, if the leaf lies under it.
+    fn path_below(&self, root: Option<u64>) -> Option<&[(u64, usize, bool)]> {
+        let root = root?;
+        if self.key == root {
+            return Some(&[]);
+        }
+        let start = self.path.iter().position(|&(key, _, _)| key == root)?;
+        Some(&self.path[start..])
+    }
+}
+
+enum Descent {
+    /// Ended on a known terminal position; its value has already been backed up.
+    BackedUp,
+    /// Needs a network evaluation before it can be backed up.
+    Leaf(Box<PendingLeaf>),
+    /// Reached a position another leaf of the same batch is already waiting on.
+    Collision,
+}
+
+/// Outcome of one search, in the caller's perspective: `q` and `pv` players use the numbering
+/// the search was called with.
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchResult {
+    pub version: u8,
+    pub policy: Vec<f32>,
+    pub q: [f32; 2],
+    pub visits: Vec<u32>,
+    pub full_search: bool,
+    /// Simulations actually completed, which a time budget can push below or above
+    /// `num_simulations`.
+    pub simulations: u32,
+    /// Simulations of the budget skipped by the `early_stop` rule.
+    pub simulations_saved: u32,
+    /// Whether the search was cut short through its [`SearchAbort`] handle.
+    pub aborted: bool,
+    /// Proven result for the root player, e.g. a forced win in `plies` plies starting with
+    /// `action`. The policy then plays the winning move.
+    pub proof: Option<Proof>,
+    /// Expected lines for the `multi_pv` most-visited root moves, best first.
+    pub pv: Vec<Vec<PvStep>>,
+    /// Final statistics of every legal root move, most visited first.
+    pub edges: Vec<EdgeStats>,
+    pub transpositions: TranspositionStats,
+}
+
+/// Search statistics of one root edge, values from the root player's point of view.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct EdgeStats {
+    pub action: usize,
+    /// Network prior after normalisation (and Dirichlet noise, if any).
+    pub prior: f32,
+    /// Mean backed-up value, `None` for edges the search never tried.
+    pub q: Option<f32>,
+    pub visits: u32,
+    /// Selection score `Q + U` the edge would get at the next simulation.
+    pub ucb: f32,
+}
+
+/// One ply of a principal variation.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct PvStep {
+    pub action: usize,
+    /// Player (0 or 1, in the caller's numbering) making this move.
+    pub player: u8,
+    /// Edge value from the moving player's point of view.
+    pub q: f32,
+    pub visits: u32,
+}
+
+/// Interim snapshot passed to the `onProgress` callback, in the same perspective as
+/// [`SearchResult`].
 #[derive(Serialize)]
-struct SearchResult {
-    version: u8,
-    policy: Vec<f32>,
+struct SearchProgress {
+    simulations: u32,
+    best_action: Option<usize>,
     q: [f32; 2],
     visits: Vec<u32>,
-    full_search: bool,
+}
+
+/// Cancels a running search. The handle is a separate object obtained via `abortHandle()` (or
+/// [`Mcts::abort_handle`] natively), so it can be handed to code that never sees the engine.
+/// The flag is checked between simulations and cleared once a search has stopped, so an abort
+/// requested while no search is running cancels the next one.
+#[wasm_bindgen]
+pub struct SearchAbort {
+    flag: Rc<Cell<bool>>,
+}
+
+#[wasm_bindgen]
+impl SearchAbort {
+    pub fn abort(&self) {
+        self.flag.set(true);
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn aborted(&self) -> bool {
+        self.flag.get()
+    }
+
+    /// Withdraw an abort that no search has consumed yet.
+    pub fn reset(&self) {
+        self.flag.set(false);
+    }
+}
+
+/// Clears the abort flag when dropped, so a search consumes its abort however it ends.
+struct AbortReset(Rc<Cell<bool>>);
+
+impl Drop for AbortReset {
+    fn drop(&mut self) {
+        self.0.set(false);
+    }
+}
+
+/// When a search stops: never before `min_simulations`, never after `max_simulations`, and in
+/// between as soon as the wall clock passes `deadline_ms`.
+struct SearchBudget {
+    min_simulations: u32,
+    max_simulations: u32,
+    deadline_ms: Option<f64>,
+}
+
+impl SearchBudget {
+    /// Exactly `count` simulations, unless `early_stop` finds the rest cannot change the move.
+    fn simulations(count: u32) -> Self {
+        Self {
+            min_simulations: 0,
+            max_simulations: count,
+            deadline_ms: None,
+        }
+    }
+
+    /// `searchFor` budget: `budget_ms` from now, at least `min_simulations` (default 1) and at
+    /// most `max_simulations` (default unbounded).
+    fn timed(budget_ms: f64, min_simulations: Option<u32>, max_simulations: Option<u32>) -> Self {
+        let min_simulations = min_simulations.unwrap_or(1).max(1);
+        Self {
+            min_simulations,
+            max_simulations: max_simulations.unwrap_or(u32::MAX).max(min_simulations),
+            deadline_ms: Some(clock::now_ms() + budget_ms.max(0.0)),
+        }
+    }
+
+    /// Best guess at how many more simulations will run, for schedules that need one up front.
+    fn expected_remaining(&self, completed: u32, fallback: u32) -> u32 {
+        let total = if self.max_simulations == u32::MAX {
+            fallback.max(self.min_simulations)
+        } else {
+            self.max_simulations
+        };
+        total.saturating_sub(completed).max(1)
+    }
+
+    fn allows(&self, completed: u32) -> bool {
+        if completed >= self.max_simulations {
+            return false;
+        }
+        completed < self.min_simulations
+            || self
+                .deadline_ms
+                .is_none_or(|deadline| clock::now_ms() < deadline)
+    }
+}
+
+/// Node-table lookups made while descending during one search. Nodes are keyed by position only,
+/// so `hits` also counts positions carried over from earlier searches or reached by another
+/// move order.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct TranspositionStats {
+    pub probes: u32,
+    pub hits: u32,
+    /// Size of the node table once the search finished.
+    pub nodes: u32,
 }
 
 #[wasm_bindgen]
 pub struct SantoriniMcts {
+    /// Borrowed only while a batch is gathered or expanded, never across the wait on the
+    /// predictor, so `stop` and `advance` can reach the tree while a ponder is running.
+    state: RefCell<Mcts<LeafEvaluator>>,
+    progress: RefCell<Option<(js_sys::Function, u32)>>,
+}
+
+/// What a [`SantoriniMcts`] evaluates its leaves with.
+enum LeafEvaluator {
+    Js(JsPredictor),
+    /// No predictor was supplied: play on rules of thumb alone.
+    Heuristic(HeuristicEvaluator),
+    #[cfg(feature = "onnx")]
+    Onnx(Box<OnnxEvaluator>),
+}
+
+impl AsyncEvaluator for LeafEvaluator {
+    type Error = Box<dyn std::error::Error>;
+    type Pending = Pin<Box<dyn Future<Output = Result<Vec<NetworkPrediction>, Self::Error>>>>;
+
+    fn request(&mut self, boards: &[i8], valid: &[u8], count: usize) -> Self::Pending {
+        match self {
+            Self::Js(predictor) => {
+                let pending = predictor.request(boards, valid, count);
+                Box::pin(async move { pending.await.map_err(Into::into) })
+            }
+            Self::Heuristic(heuristic) => {
+                let predictions = heuristic.evaluate_batch(boards, valid, count);
+                Box::pin(std::future::ready(predictions.map_err(Into::into)))
+            }
+            #[cfg(feature = "onnx")]
+            Self::Onnx(model) => {
+                let predictions = model.evaluate_batch(boards, valid, count);
+                Box::pin(std::future::ready(predictions.map_err(Into::into)))
+            }
+        }
+    }
+}
+
+/// Search engine generic over its leaf evaluator. [`SantoriniMcts`] drives one backed by the
+/// JavaScript predictor; native code can drive one with any [`Evaluator`] through
+/// [`Mcts::search`] and [`Mcts::search_for`], or with any [`AsyncEvaluator`] through
+/// [`Mcts::search_async`], [`Mcts::search_for_async`] and [`Mcts::ponder`].
+///
+/// The engine is `!Send`: it shares its abort flag with the [`SearchAbort`] handles it gives out
+/// through an `Rc<Cell<bool>>`, so it and its handles stay on one thread.
+pub struct Mcts<E> {
     config: MctsConfig,
-    predictor: js_sys::Function,
+    evaluator: E,
+    abort: Rc<Cell<bool>>,
     rng: SmallRng,
-    nodes: HashMap<[i8; STATE_SIZE], TreeNode>,
-    last_cleanup_round: u16,
+    nodes: NodeTable,
+    /// Debug-only record of the position behind every key, used to catch Zobrist collisions.
+    #[cfg(debug_assertions)]
+    key_guard: HashMap<u64, BoardState, BuildZobristHasher>,
+    stats: TranspositionStats,
+    in_flight: HashMap<u64, InFlight, BuildZobristHasher>,
+    /// Canonical position the tree is currently rooted at, if any.
+    root: Option<BoardState>,
+    /// Bumped by anything that invalidates the running search or ponder. A run from an older
+    /// generation stops and drops the evaluations it was still waiting on.
+    generation: u32,
     board_buffer: Vec<i8>,
     mask_buffer: Vec<u8>,
 }
 
+/// Loop state of one search or ponder, carried across the awaits on the predictor.
+struct SearchRun {
+    board: BoardState,
+    root_player: usize,
+    generation: u32,
+    pondering: bool,
+    full_search: bool,
+    forced_playouts: bool,
+    gumbel: bool,
+    budget: SearchBudget,
+    halving: Option<SequentialHalving>,
+    completed: u32,
+    simulations_saved: u32,
+    /// Simulation count at the last progress report.
+    reported: u32,
+}
+
 #[wasm_bindgen]
 impl SantoriniMcts {
+    /// `predictor` is `(board: Int8Array, mask: Uint8Array) => Promise<{ pi, v }>`. Without one
+    /// the engine falls back to the built-in heuristic evaluator, so it can play with no model
+    /// downloaded at all.
     #[wasm_bindgen(constructor)]
-    pub fn new(config: JsValue, predictor: js_sys::Function) -> Result<SantoriniMcts, JsValue> {
+    pub fn new(
+        config: JsValue,
+        predictor: Option<js_sys::Function>,
+    ) -> Result<SantoriniMcts, JsValue> {
         let cfg: MctsConfig = if config.is_undefined() || config.is_null() {
             MctsConfig::default()
         } else {
             serde_wasm_bindgen::from_value(config)?
         };
+        let evaluator = match predictor {
+            Some(predictor) => LeafEvaluator::Js(JsPredictor {
+                predictor,
+                batch_predictor: None,
+            }),
+            None => LeafEvaluator::Heuristic(HeuristicEvaluator::default()),
+        };
         Ok(Self {
-            config: cfg,
-            predictor,
-            rng: SmallRng::from_entropy(),
-            nodes: HashMap::new(),
-            last_cleanup_round: 0,
-            board_buffer: vec![0; STATE_SIZE],
-            mask_buffer: vec![0; ACTION_SIZE],
+            state: RefCell::new(Mcts::new(cfg, evaluator)),
+            progress: RefCell::new(None),
         })
     }
 
+    /// Engine evaluating leaves with the ONNX model in `model` (the bytes of
+    /// `model_no_god.onnx`) inside wasm, without calling back into JavaScript.
+    #[cfg(feature = "onnx")]
+    #[wasm_bindgen(js_name = withOnnxModel)]
+    pub fn with_onnx_model(config: JsValue, model: &[u8]) -> Result<SantoriniMcts, JsValue> {
+        let engine = Self::new(config, None)?;
+        let evaluator = OnnxEvaluator::from_bytes(model)
+            .map_err(|err| JsValue::from_str(&format!("cannot load ONNX model: {err}")))?;
+        engine.state.borrow_mut().evaluator = LeafEvaluator::Onnx(Box::new(evaluator));
+        Ok(engine)
+    }
+
     #[wasm_bindgen(js_name = defaultConfig)]
     pub fn default_config() -> JsValue {
         serde_wasm_bindgen::to_value(&MctsConfig::default()).expect("config serialises")
     }
 
     #[wasm_bindgen(js_name = setSeed)]
-    pub fn set_seed(&mut self, seed: u64) {
-        self.rng = SmallRng::seed_from_u64(seed);
+    pub fn set_seed(&self, seed: u64) {
+        self.state.borrow_mut().set_seed(seed);
+    }
+
+    /// Register `(boards: Int8Array, masks: Uint8Array, count: number) => Promise<{ pi, v }>`,
+    /// called once per batch with `count` boards stacked row by row. `pi` holds `count × 162`
+    /// scores and `v` holds `count` values. Pass `undefined` to fall back to concurrent calls of
+    /// the single-board predictor. Ignored by engines without a JavaScript predictor.
+    #[wasm_bindgen(js_name = setBatchPredictor)]
+    pub fn set_batch_predictor(&self, predictor: Option<js_sys::Function>) {
+        if let LeafEvaluator::Js(js) = &mut self.state.borrow_mut().evaluator {
+            js.batch_predictor = predictor;
+        }
+    }
+
+    /// Shape the built-in heuristic's policy with move heuristics (the default), or give every
+    /// legal move the same prior. Ignored by engines with a predictor or an ONNX model.
+    #[wasm_bindgen(js_name = setShapedPolicy)]
+    pub fn set_shaped_policy(&self, shaped: bool) {
+        if let LeafEvaluator::Heuristic(heuristic) = &mut self.state.borrow_mut().evaluator {
+            heuristic.shaped_policy = shaped;
+        }
+    }
+
+    /// Handle that stops the current (or next) search after the simulations in flight.
+    #[wasm_bindgen(js_name = abortHandle)]
+    pub fn abort_handle(&self) -> SearchAbort {
+        self.state.borrow().abort_handle()
+    }
+
+    /// Register `onProgress({ simulations, best_action, q, visits })`, called at most once per
+    /// batch whenever another `interval` simulations have completed. Pass `undefined` to stop.
+    #[wasm_bindgen(js_name = setProgressCallback)]
+    pub fn set_progress_callback(&self, callback: Option<js_sys::Function>, interval: u32) {
+        *self.progress.borrow_mut() = callback.map(|callback| (callback, interval.max(1)));
+    }
+
+    /// Re-root the tree at `board_state` with `player` to move, keeping only the nodes reachable
+    /// from it. Returns the number of visits carried over at the new root.
+    #[wasm_bindgen(js_name = setRoot)]
+    pub fn set_root(&self, board_state: Vec<i8>, player: u8) -> Result<u32, JsValue> {
+        Ok(self.state.borrow_mut().set_root(&board_state, player)?)
+    }
+
+    /// Play `action` (indexed from the root player's perspective, as in search results) at the
+    /// current root and keep only its subtree. Returns the number of visits carried over.
+    /// A running ponder stops here, leaving everything it found below `action` for the reply.
+    #[wasm_bindgen(js_name = advance)]
+    pub fn advance(&self, action: usize) -> Result<u32, JsValue> {
+        Ok(self.state.borrow_mut().advance(action)?)
+    }
+
+    /// Stop a running `ponder`, keeping its tree. A search interrupted this way resolves as if
+    /// aborted.
+    #[wasm_bindgen(js_name = stop)]
+    pub fn stop(&self) {
+        self.state.borrow_mut().stop();
     }
 
     #[allow(clippy::too_many_arguments)]
     #[wasm_bindgen(js_name = search)]
     pub async fn search(
-        &mut self,
+        &self,
         board_state: Vec<i8>,
         player: u8,
         temperature: f32,
         force_full_search: bool,
     ) -> Result<JsValue, JsValue> {
-        if board_state.len() != STATE_SIZE {
-            return Err(JsValue::from_str("board state must contain 75 entries"));
+        let (full_search, budget) = self.state.borrow_mut().plan_search(force_full_search);
+        self.run_search(&board_state, player, temperature, full_search, budget)
+            .await
+    }
+
+    /// Anytime variant of [`SantoriniMcts::search`]: keep simulating until `budget_ms`
+    /// milliseconds have passed, but run at least `min_simulations` (default 1) and at most
+    /// `max_simulations` (default unbounded). Always a full search; the result's `simulations`
+    /// field reports how many were completed.
+    #[wasm_bindgen(js_name = searchFor)]
+    pub async fn search_for(
+        &self,
+        board_state: Vec<i8>,
+        player: u8,
+        budget_ms: f64,
+        temperature: f32,
+        min_simulations: Option<u32>,
+        max_simulations: Option<u32>,
+    ) -> Result<JsValue, JsValue> {
+        let budget = SearchBudget::timed(budget_ms, min_simulations, max_simulations);
+        self.run_search(&board_state, player, temperature, true, budget)
+            .await
+    }
+
+    /// Search `board_state` on the opponent's time, with `player` (the opponent) to move, until
+    /// `stop()` or `advance(action)` is called or `max_simulations` (default unbounded) have
+    /// run. Resolves to the number of simulations completed. Pondering adds no root noise and
+    /// always uses PUCT at the root; the tree it grows is reused by the next search.
+    #[wasm_bindgen(js_name = ponder)]
+    pub async fn ponder(
+        &self,
+        board_state: Vec<i8>,
+        player: u8,
+        max_simulations: Option<u32>,
+    ) -> Result<u32, JsValue> {
+        Mcts::ponder_reporting(&self.state, &board_state, player, max_simulations, |run| {
+            self.report_progress(run)
+        })
+        .await
+    }
+}
+
+impl SantoriniMcts {
+    async fn run_search(
+        &self,
+        board_state: &[i8],
+        player: u8,
+        temperature: f32,
+        full_search: bool,
+        budget: SearchBudget,
+    ) -> Result<JsValue, JsValue> {
+        let result = Mcts::search_reporting(
+            &self.state,
+            board_state,
+            player,
+            temperature,
+            full_search,
+            budget,
+            |run| self.report_progress(run),
+        )
+        .await?;
+        serde_wasm_bindgen::to_value(&result).map_err(JsValue::from)
+    }
+
+    /// Hand the `onProgress` callback a snapshot if another interval has passed. Called
+    /// without any borrow held, so the callback may use the engine.
+    fn report_progress(&self, run: &mut SearchRun) -> Result<(), JsValue> {
+        let progress = self.progress.borrow().clone();
+        if let Some((callback, every)) = progress {
+            let report = self.state.borrow().progress_report(run, every);
+            if let Some(report) = report {
+                callback.call1(&JsValue::NULL, &serde_wasm_bindgen::to_value(&report)?)?;
+            }
         }
-        let mut board = BoardState::from_vec(&board_state);
-        let root_player = player as usize;
-        if root_player != 0 {
-            board = board.canonicalised(root_player);
+        Ok(())
+    }
+}
+
+impl<E> Mcts<E> {
+    pub fn new(config: MctsConfig, evaluator: E) -> Self {
+        Self {
+            config,
+            evaluator,
+            abort: Rc::new(Cell::new(false)),
+            rng: SmallRng::from_entropy(),
+            nodes: HashMap::default(),
+            #[cfg(debug_assertions)]
+            key_guard: HashMap::default(),
+            stats: TranspositionStats::default(),
+            in_flight: HashMap::default(),
+            root: None,
+            generation: 0,
+            board_buffer: vec![0; STATE_SIZE],
+            mask_buffer: vec![0; ACTION_SIZE],
         }
+    }
+
+    pub fn set_seed(&mut self, seed: u64) {
+        self.rng = SmallRng::seed_from_u64(seed);
+    }
+
+    /// See `SantoriniMcts.abortHandle`.
+    pub fn abort_handle(&self) -> SearchAbort {
+        SearchAbort {
+            flag: Rc::clone(&self.abort),
+        }
+    }
+
+    /// See `SantoriniMcts.setRoot`.
+    pub fn set_root(&mut self, board_state: &[i8], player: u8) -> Result<u32, SantoriniError> {
+        let board = BoardState::try_from_bytes(board_state)?;
+        board.validate()?;
+        if player > 1 {
+            return Err(SantoriniError::InvalidPlayer {
+                player: player as usize,
+            });
+        }
+        self.interrupt();
+        Ok(self.reroot(board.canonicalised(player as usize)))
+    }
+
+    /// See `SantoriniMcts.advance`.
+    pub fn advance(&mut self, action: usize) -> Result<u32, SearchError> {
+        let mut board = self.root.ok_or(SearchError::NoRoot)?;
+        let next_player = board.try_make_move(action, 0)?;
+        self.interrupt();
+        Ok(self.reroot(board.canonicalised(next_player)))
+    }
+
+    /// See `SantoriniMcts.stop`.
+    pub fn stop(&mut self) {
+        self.interrupt();
+    }
+
+    fn abort_reset(&self) -> AbortReset {
+        AbortReset(Rc::clone(&self.abort))
+    }
 
+    /// Roll between a full and a partial search, as configured by `prob_full_search`.
+    fn plan_search(&mut self, force_full_search: bool) -> (bool, SearchBudget) {
         let mut full_search = force_full_search;
         if !full_search {
             let roll: f32 = self.rng.gen();
@@ -357,172 +1087,848 @@ impl SantoriniMcts {
         if !full_search {
             num_sims = (num_sims / self.config.partial_divisor.max(1)).max(1);
         }
-        let forced_playouts = full_search && self.config.forced_playouts;
+        (full_search, SearchBudget::simulations(num_sims))
+    }
 
-        for sim in 0..num_sims {
-            let inject_dirichlet = sim == 0 && full_search && self.config.dirichlet_weight > 0.0;
-            self.run_single_simulation(&board, inject_dirichlet, sim + 1, forced_playouts)
-                .await?;
+    fn begin_search(
+        &mut self,
+        board_state: &[i8],
+        player: u8,
+        full_search: bool,
+        pondering: bool,
+        mut budget: SearchBudget,
+    ) -> Result<SearchRun, SantoriniError> {
+        let mut board = BoardState::try_from_bytes(board_state)?;
+        board.validate()?;
+        let root_player = player as usize;
+        if root_player != 0 {
+            board = board.canonicalised(root_player);
         }
 
-        if !self.config.no_mem_optim {
-            self.maybe_cleanup(board.round());
-        }
+        self.interrupt();
+        self.reroot(board);
+        self.stats = TranspositionStats::default();
 
-        let key = board.key();
-        let (valid, policy_prior, edge_visits, q) = {
-            let node_ref = self
-                .nodes
-                .get(&key)
-                .ok_or_else(|| JsValue::from_str("root node missing after simulations"))?;
-            (
-                node_ref.valid,
-                node_ref.policy,
-                node_ref.nsa,
-                node_ref.mean_value,
-            )
+        let mut simulations_saved = 0;
+        let forced_move = !pondering && self.config.early_stop && {
+            let mut moves = board.legal_moves(0);
+            moves.next().is_some() && moves.next().is_none()
         };
-
-        let (policy, visits) = self.root_distribution(
-            &valid,
-            &policy_prior,
-            &edge_visits,
-            temperature,
-            forced_playouts,
-            num_sims,
-        );
-        let green_value = if root_player == 0 { q } else { -q };
-        let result = SearchResult {
-            version: SEARCH_RESULT_VERSION,
-            policy,
-            q: [green_value, -green_value],
-            visits,
+        if forced_move {
+            // Only the root's value is worth a simulation, and only if the tree lacks it.
+            let needed = u32::from(!self.nodes.contains_key(&board.hash()));
+            simulations_saved = budget
+                .expected_remaining(0, self.config.num_simulations)
+                .saturating_sub(needed);
+            budget = SearchBudget::simulations(needed);
+        }
+        Ok(SearchRun {
+            board,
+            root_player,
+            generation: self.generation,
+            pondering,
             full_search,
-        };
-        serde_wasm_bindgen::to_value(&result).map_err(JsValue::from)
+            forced_playouts: full_search && self.config.forced_playouts,
+            gumbel: !pondering && self.config.root_policy == RootPolicy::Gumbel,
+            budget,
+            halving: None,
+            completed: 0,
+            simulations_saved,
+            reported: 0,
+        })
     }
-}
 
-impl SantoriniMcts {
-    async fn run_single_simulation(
-        &mut self,
-        root: &BoardState,
-        apply_dirichlet: bool,
-        iteration: u32,
-        forced_playouts: bool,
-    ) -> Result<f32, JsValue> {
+    /// Invalidate whatever search or ponder is running and forget its pending leaves.
+    fn interrupt(&mut self) {
+        self.generation = self.generation.wrapping_add(1);
+        self.in_flight.clear();
+    }
+
+    /// Gather the next batch of leaves for `run`, or `None` once it should stop. The batch may
+    /// be empty when every descent ended on a known result.
+    fn next_batch(&mut self, run: &mut SearchRun) -> Option<Vec<PendingLeaf>> {
+        let board = run.board;
+        // A settled root needs no more simulations, so the search may stop short of its budget.
+        if run.generation != self.generation
+            || (!run.pondering && self.abort.get())
+            || self.is_settled(&board)
+            || !run.budget.allows(run.completed)
+        {
+            return None;
+        }
+        if self.config.early_stop
+            && !run.pondering
+            && !run.gumbel
+            && run.budget.max_simulations != u32::MAX
+        {
+            let remaining = run.budget.max_simulations.saturating_sub(run.completed);
+            let margin = self
+                .nodes
+                .get(&board.hash())
+                .map_or(0, TreeNode::leader_margin);
+            if run.completed >= run.budget.min_simulations && margin > remaining {
+                run.simulations_saved = remaining;
+                return None;
+            }
+        }
+
+        let batch_size = self.config.batch_size.max(1) as usize;
+        let transform = self.q_transform();
+        let known_nodes = self.nodes.len();
+        let mut leaves: Vec<PendingLeaf> = Vec::with_capacity(batch_size);
+        while run.budget.allows(run.completed)
+            && leaves.len() < batch_size
+            && !self.is_settled(&board)
+        {
+            let inject_dirichlet = run.completed == 0
+                && run.full_search
+                && !run.gumbel
+                && self.config.dirichlet_weight > 0.0;
+            // Gumbel schedules start once the root has a prior to sample from, and only sample
+            // moves not yet proven to lose. A proven win needs no schedule: the result plays it.
+            let root_action = match self.nodes.get(&board.hash()) {
+                Some(root) if run.gumbel && !root.solver.proof.is_some_and(|proof| proof.won) => {
+                    let lost = |action| root.solver.is_lost(action);
+                    let halving = run.halving.get_or_insert_with(|| {
+                        let mut candidates = root.valid;
+                        for (action, candidate) in candidates.iter_mut().enumerate() {
+                            *candidate &= !lost(action);
+                        }
+                        SequentialHalving::new(
+                            &mut self.rng,
+                            &root.policy,
+                            &candidates,
+                            self.config.gumbel_max_considered as usize,
+                            run.budget
+                                .expected_remaining(run.completed, self.config.num_simulations),
+                            transform,
+                        )
+                    });
+                    halving.retire(lost);
+                    halving.next_action(&root.nsa, &root.qsa)
+                }
+                _ => None,
+            };
+            match self.descend(
+                &board,
+                inject_dirichlet,
+                run.completed + 1,
+                run.forced_playouts,
+                root_action,
+                &leaves,
+            ) {
+                Descent::BackedUp => {}
+                Descent::Leaf(leaf) => {
+                    self.add_virtual_loss(&leaf.path);
+                    leaves.push(*leaf);
+                }
+                // The batch has run out of distinct leaves; evaluate what we have.
+                Descent::Collision => {
+                    if let (Some(halving), Some(action)) = (run.halving.as_mut(), root_action) {
+                        halving.requeue(action);
+                    }
+                    break;
+                }
+            }
+            run.completed += 1;
+        }
+        // Below an unsolved root every backed-up descent proves or refutes something new. A
+        // solved root can keep drawing descents into lines it already knows, so a batch that
+        // adds nothing means no later one will either (and `searchFor` would spin until its
+        // deadline).
+        let solved = self
+            .nodes
+            .get(&board.hash())
+            .is_some_and(|root| root.solver.proof.is_some());
+        if solved && leaves.is_empty() && self.nodes.len() == known_nodes {
+            return None;
+        }
+        Some(leaves)
+    }
+
+    fn finish_search(
+        &mut self,
+        mut run: SearchRun,
+        temperature: f32,
+    ) -> Result<SearchResult, SearchError> {
+        let SearchRun {
+            board,
+            root_player,
+            full_search,
+            forced_playouts,
+            completed,
+            simulations_saved,
+            ..
+        } = run;
+        // Interrupted searches report what they have, like aborted ones.
+        let interrupted = run.generation != self.generation;
+        let aborted = self.abort.get() || interrupted;
+
+        let key = board.hash();
+        let root_stats = self
+            .nodes
+            .get(&key)
+            .map(|node| (node.valid, node.policy, node.nsa, node.qsa, node.mean_value));
+        let searched = root_stats.is_some();
+        let (valid, policy_prior, edge_visits, edge_values, q) = match root_stats {
+            Some(stats) => stats,
+            // Aborted before the root was evaluated: nothing to weigh.
+            None if aborted => {
+                let mut valid = [false; ACTION_SIZE];
+                board.valid_moves(0, &mut valid);
+                (
+                    valid,
+                    [0.0; ACTION_SIZE],
+                    [0; ACTION_SIZE],
+                    [0.0; ACTION_SIZE],
+                    0.0,
+                )
+            }
+            None => return Err(SearchError::MissingRoot),
+        };
+
+        let (mut policy, visits) = self.root_distribution(
+            &valid,
+            &policy_prior,
+            &edge_visits,
+            temperature,
+            forced_playouts,
+            completed,
+        );
+        if !searched {
+            let legal = valid.iter().filter(|&&flag| flag).count().max(1) as f32;
+            for (share, &flag) in policy.iter_mut().zip(&valid) {
+                *share = if flag { 1.0 / legal } else { 0.0 };
+            }
+        }
+        if let (Some(halving), Some(root)) = (run.halving.as_mut(), self.nodes.get(&key)) {
+            halving.retire(|action| root.solver.is_lost(action));
+            let mut playable = valid;
+            for (action, flag) in playable.iter_mut().enumerate() {
+                *flag &= !root.solver.is_lost(action);
+            }
+            // A lost root has nothing better to offer than its plain legal moves.
+            if !playable.contains(&true) {
+                playable = valid;
+            }
+            if temperature != 0.0 {
+                policy = improved_policy(
+                    &policy_prior,
+                    &playable,
+                    &edge_visits,
+                    &edge_values,
+                    q,
+                    temperature,
+                    self.q_transform(),
+                );
+            } else if let Some(action) = halving.best(&edge_visits, &edge_values) {
+                policy.fill(0.0);
+                policy[action] = 1.0;
+            }
+        }
+        let proof = self.nodes.get(&key).and_then(|node| node.solver.proof);
+        if let Some(Proof {
+            won: true,
+            action: Some(winning),
+            ..
+        }) = proof
+        {
+            policy.fill(0.0);
+            policy[winning] = 1.0;
+        }
+        let green_value = if root_player == 0 { q } else { -q };
+        Ok(SearchResult {
+            version: SEARCH_RESULT_VERSION,
+            policy,
+            q: [green_value, -green_value],
+            visits,
+            full_search,
+            simulations: completed,
+            simulations_saved,
+            aborted,
+            proof,
+            pv: principal_variations(
+                &self.nodes,
+                &board,
+                root_player as u8,
+                self.config.multi_pv as usize,
+            ),
+            edges: self.nodes.get(&key).map_or_else(Vec::new, |node| {
+                node.edge_stats(self.config.cpuct, self.config.fpu_reduction)
+            }),
+            transpositions: TranspositionStats {
+                nodes: self.nodes.len() as u32,
+                ..self.stats
+            },
+        })
+    }
+
+    fn q_transform(&self) -> QTransform {
+        QTransform {
+            c_visit: self.config.gumbel_c_visit,
+            c_scale: self.config.gumbel_c_scale,
+        }
+    }
+}
+
+impl<E: Evaluator> Mcts<E> {
+    /// Native counterpart of `SantoriniMcts.search`, evaluating leaves on the calling thread.
+    pub fn search(
+        &mut self,
+        board_state: &[i8],
+        player: u8,
+        temperature: f32,
+        force_full_search: bool,
+    ) -> Result<SearchResult, SearchError> {
+        let (full_search, budget) = self.plan_search(force_full_search);
+        self.run_search(board_state, player, temperature, full_search, budget)
+    }
+
+    /// Native counterpart of `SantoriniMcts.searchFor`.
+    pub fn search_for(
+        &mut self,
+        board_state: &[i8],
+        player: u8,
+        budget_ms: f64,
+        temperature: f32,
+        min_simulations: Option<u32>,
+        max_simulations: Option<u32>,
+    ) -> Result<SearchResult, SearchError> {
+        let budget = SearchBudget::timed(budget_ms, min_simulations, max_simulations);
+        self.run_search(board_state, player, temperature, true, budget)
+    }
+
+    fn run_search(
+        &mut self,
+        board_state: &[i8],
+        player: u8,
+        temperature: f32,
+        full_search: bool,
+        budget: SearchBudget,
+    ) -> Result<SearchResult, SearchError> {
+        let _reset = self.abort_reset();
+        let mut run = self.begin_search(board_state, player, full_search, false, budget)?;
+        while let Some(leaves) = self.next_batch(&mut run) {
+            if leaves.is_empty() {
+                continue;
+            }
+            self.stage_leaves(&leaves);
+            let predictions = self
+                .evaluator
+                .evaluate_batch(&self.board_buffer, &self.mask_buffer, leaves.len())
+                .map_err(|err| SearchError::Evaluator(err.into()))?;
+            self.expand_leaves(&mut run, leaves, predictions)?;
+        }
+        self.finish_search(run, temperature)
+    }
+}
+
+impl<E: AsyncEvaluator> Mcts<E> {
+    /// [`Mcts::search`] for evaluators that answer asynchronously; `SantoriniMcts.search` runs
+    /// on the same loop. Like [`Mcts::ponder`], `engine` is only borrowed between awaits.
+    pub async fn search_async(
+        engine: &RefCell<Self>,
+        board_state: &[i8],
+        player: u8,
+        temperature: f32,
+        force_full_search: bool,
+    ) -> Result<SearchResult, SearchError> {
+        let (full_search, budget) = engine.borrow_mut().plan_search(force_full_search);
+        Self::search_reporting(
+            engine,
+            board_state,
+            player,
+            temperature,
+            full_search,
+            budget,
+            |_| Ok(()),
+        )
+        .await
+    }
+
+    /// [`Mcts::search_for`] for evaluators that answer asynchronously.
+    pub async fn search_for_async(
+        engine: &RefCell<Self>,
+        board_state: &[i8],
+        player: u8,
+        budget_ms: f64,
+        temperature: f32,
+        min_simulations: Option<u32>,
+        max_simulations: Option<u32>,
+    ) -> Result<SearchResult, SearchError> {
+        let budget = SearchBudget::timed(budget_ms, min_simulations, max_simulations);
+        Self::search_reporting(
+            engine,
+            board_state,
+            player,
+            temperature,
+            true,
+            budget,
+            |_| Ok(()),
+        )
+        .await
+    }
+
+    /// Native counterpart of `SantoriniMcts.ponder`. `engine` is only borrowed while a batch is
+    /// gathered or expanded, so [`Mcts::stop`], [`Mcts::advance`] or another search can reach it
+    /// while the ponder is pending.
+    pub async fn ponder(
+        engine: &RefCell<Self>,
+        board_state: &[i8],
+        player: u8,
+        max_simulations: Option<u32>,
+    ) -> Result<u32, SearchError> {
+        Self::ponder_reporting(engine, board_state, player, max_simulations, |_| Ok(())).await
+    }
+
+    /// [`Mcts::search_async`] with a planned budget, calling `report` after every batch.
+    async fn search_reporting<X: From<SearchError>>(
+        engine: &RefCell<Self>,
+        board_state: &[i8],
+        player: u8,
+        temperature: f32,
+        full_search: bool,
+        budget: SearchBudget,
+        report: impl FnMut(&mut SearchRun) -> Result<(), X>,
+    ) -> Result<SearchResult, X> {
+        let _reset = engine.borrow().abort_reset();
+        let mut run = engine
+            .borrow_mut()
+            .begin_search(board_state, player, full_search, false, budget)
+            .map_err(SearchError::from)?;
+        Self::drive(engine, &mut run, report).await?;
+        Ok(engine.borrow_mut().finish_search(run, temperature)?)
+    }
+
+    /// [`Mcts::ponder`], calling `report` after every batch.
+    async fn ponder_reporting<X: From<SearchError>>(
+        engine: &RefCell<Self>,
+        board_state: &[i8],
+        player: u8,
+        max_simulations: Option<u32>,
+        report: impl FnMut(&mut SearchRun) -> Result<(), X>,
+    ) -> Result<u32, X> {
+        let budget = SearchBudget {
+            min_simulations: 0,
+            max_simulations: max_simulations.unwrap_or(u32::MAX),
+            deadline_ms: None,
+        };
+        let mut run = engine
+            .borrow_mut()
+            .begin_search(board_state, player, false, true, budget)
+            .map_err(SearchError::from)?;
+        Self::drive(engine, &mut run, report).await?;
+        Ok(run.completed)
+    }
+
+    /// Run batches until `run` is done or interrupted, calling `report` after each one. Each
+    /// batch borrows `engine` twice, once to gather and once to expand, and releases it while
+    /// the evaluator works. Ponders, and every run whose evaluator has answered on the spot
+    /// (its request was ready when first polled) since the last yield, hand the event loop back
+    /// every [`YIELD_SLICE_MS`]; otherwise `stop()`, aborts and progress repaints would have to
+    /// wait for the whole run.
+    async fn drive<X: From<SearchError>>(
+        engine: &RefCell<Self>,
+        run: &mut SearchRun,
+        mut report: impl FnMut(&mut SearchRun) -> Result<(), X>,
+    ) -> Result<(), X> {
+        let mut slice_start = clock::now_ms();
+        let mut suspended = false;
+        loop {
+            if (run.pondering || !suspended) && clock::now_ms() - slice_start >= YIELD_SLICE_MS {
+                clock::yield_to_event_loop().await;
+                slice_start = clock::now_ms();
+                suspended = false;
+            }
+            let batch = engine.borrow_mut().next_batch(run);
+            let Some(leaves) = batch else {
+                return Ok(());
+            };
+            if !leaves.is_empty() {
+                let mut pending = pin!(engine.borrow_mut().request_evaluation(&leaves));
+                let predictions = poll_fn(|cx| {
+                    let poll = pending.as_mut().poll(cx);
+                    suspended |= poll.is_pending();
+                    poll
+                })
+                .await
+                .map_err(|err| SearchError::Evaluator(err.into()))?;
+                engine
+                    .borrow_mut()
+                    .expand_leaves(run, leaves, predictions)?;
+            }
+            report(run)?;
+        }
+    }
+
+    /// Hand `leaves` to the evaluator. The returned future holds no borrow of the engine.
+    fn request_evaluation(&mut self, leaves: &[PendingLeaf]) -> E::Pending {
+        self.stage_leaves(leaves);
+        self.evaluator
+            .request(&self.board_buffer, &self.mask_buffer, leaves.len())
+    }
+}
+
+type NodeTable = HashMap<u64, TreeNode, BuildZobristHasher>;
+
+/// Lines starting with each of the `count` most-visited root moves, each continued by always
+/// following the most-visited edge until the tree runs out.
+fn principal_variations(
+    nodes: &NodeTable,
+    root: &BoardState,
+    root_player: u8,
+    count: usize,
+) -> Vec<Vec<PvStep>> {
+    let Some(node) = nodes.get(&root.hash()) else {
+        return Vec::new();
+    };
+    let mut first_moves: Vec<usize> = (0..ACTION_SIZE)
+        .filter(|&action| node.valid[action] && node.nsa[action] > 0)
+        .collect();
+    first_moves.sort_by_key(|&action| std::cmp::Reverse(node.nsa[action]));
+    first_moves.truncate(count);
+
+    first_moves
+        .into_iter()
+        .map(|first| {
+            let mut line = Vec::new();
+            let mut board = *root;
+            let mut player = root_player;
+            let mut action = Some(first);
+            while let Some(chosen) = action {
+                let Some(node) = nodes.get(&board.hash()) else {
+                    break;
+                };
+                line.push(PvStep {
+                    action: chosen,
+                    player,
+                    q: node.qsa[chosen],
+                    visits: node.nsa[chosen],
+                });
+                let next_player = board.make_move(chosen, 0);
+                if next_player == 1 {
+                    player = 1 - player;
+                }
+                board = board.canonicalised(next_player);
+                action = nodes.get(&board.hash()).and_then(|child| {
+                    (0..ACTION_SIZE)
+                        .filter(|&a| child.valid[a] && child.nsa[a] > 0)
+                        .max_by_key(|&a| child.nsa[a])
+                });
+            }
+            line
+        })
+        .collect()
+}
+
+/// Keep only the nodes reachable from `root` through edges the search has actually taken.
+fn prune_to_subtree(nodes: &mut NodeTable, root: &BoardState) {
+    let mut reachable: HashSet<u64, BuildZobristHasher> = HashSet::default();
+    let mut stack = Vec::new();
+    if nodes.contains_key(&root.hash()) {
+        reachable.insert(root.hash());
+        stack.push(*root);
+    }
+    while let Some(board) = stack.pop() {
+        let Some(node) = nodes.get(&board.hash()) else {
+            continue;
+        };
+        for action in 0..ACTION_SIZE {
+            if !node.valid[action] || node.nsa[action] == 0 {
+                continue;
+            }
+            let mut child = board;
+            let next_player = child.make_move(action, 0);
+            let child = child.canonicalised(next_player);
+            let key = child.hash();
+            if nodes.contains_key(&key) && reachable.insert(key) {
+                stack.push(child);
+            }
+        }
+    }
+    let root_round = root.round();
+    nodes.retain(|key, node| node.round >= root_round && reachable.contains(key));
+}
+
+impl<E> Mcts<E> {
+    /// Walk from `root` to the first unexpanded position. Terminal positions are expanded and
+    /// backed up on the spot; anything else is handed back as a leaf awaiting evaluation, unless
+    /// another leaf of the current batch (`pending`) already claimed the same position.
+    fn descend(
+        &mut self,
+        root: &BoardState,
+        apply_dirichlet: bool,
+        iteration: u32,
+        forced_playouts: bool,
+        root_action: Option<usize>,
+        pending: &[PendingLeaf],
+    ) -> Descent {
         let mut board = *root;
-        let mut to_root_sign = 1.0f32;
-        let mut breadcrumbs: Vec<([i8; STATE_SIZE], usize, bool)> = Vec::with_capacity(32);
+        let mut path: Vec<(u64, usize, bool)> = Vec::with_capacity(32);
 
         loop {
-            let key = board.key();
+            let key = board.hash();
+            self.guard_key(key, &board);
+            self.stats.probes += 1;
             if let Some(node) = self.nodes.get_mut(&key) {
-                if apply_dirichlet && breadcrumbs.is_empty() {
+                self.stats.hits += 1;
+                node.round = node.round.max(board.round());
+                if apply_dirichlet && path.is_empty() {
                     node.apply_dirichlet(
                         &mut self.rng,
                         self.config.dirichlet_alpha,
                         self.config.dirichlet_weight,
                     );
                 }
-                if let Some(result) = node.terminal_value {
-                    self.backpropagate(&breadcrumbs, result);
-                    return Ok(result * to_root_sign);
+                // A proven root keeps searching for a quicker win until it is settled.
+                if let Some(proof) = node.solver.proof {
+                    if !path.is_empty() || node.settled() {
+                        let result = node.terminal_value.unwrap_or(proof.value());
+                        self.backpropagate(&path, result, Some(proof));
+                        return Descent::BackedUp;
+                    }
                 }
-                let action = node.select_action(
-                    self.config.cpuct,
-                    self.config.fpu_reduction,
-                    forced_playouts,
-                    iteration,
-                    self.config.forced_playout_coefficient,
-                );
+                let action = match root_action.filter(|_| path.is_empty()) {
+                    Some(action) => action,
+                    None => node.select_action(
+                        self.config.cpuct,
+                        self.config.fpu_reduction,
+                        forced_playouts,
+                        iteration,
+                        self.config.forced_playout_coefficient,
+                        self.in_flight.get(&key),
+                        self.config.virtual_loss,
+                    ),
+                };
                 let next_player = board.make_move(action, 0);
                 // When `next_player == 1` we flipped perspective to keep the canonical player always 0.
-                breadcrumbs.push((key, action, next_player == 1));
-                if next_player == 1 {
-                    to_root_sign = -to_root_sign;
-                }
+                path.push((key, action, next_player == 1));
                 board = board.canonicalised(next_player);
                 continue;
             }
 
+            if pending.iter().any(|leaf| leaf.key == key) {
+                return Descent::Collision;
+            }
+
             let mut valid = [false; ACTION_SIZE];
             board.valid_moves(0, &mut valid);
             if let Some(terminal) = board.result_value(0) {
                 let node = TreeNode::terminal(valid, terminal, board.round());
+                let proof = node.solver.proof;
                 self.nodes.insert(key, node);
-                self.backpropagate(&breadcrumbs, terminal);
-                return Ok(terminal * to_root_sign);
+                self.backpropagate(&path, terminal, proof);
+                return Descent::BackedUp;
+            }
+            if let Some(action) = board.winning_move(0) {
+                let proof = Proof {
+                    won: true,
+                    plies: 1,
+                    action: Some(action),
+                };
+                self.nodes
+                    .insert(key, TreeNode::solved(valid, proof, board.round()));
+                self.backpropagate(&path, proof.value(), Some(proof));
+                return Descent::BackedUp;
             }
 
-            let prediction = self.evaluate(&board, &valid).await?;
-            let node = TreeNode::from_prediction(valid, &prediction, board.round());
-            let leaf_value = node.mean_value;
-            self.nodes.insert(key, node);
-            self.backpropagate(&breadcrumbs, leaf_value);
-            return Ok(leaf_value * to_root_sign);
+            return Descent::Leaf(Box::new(PendingLeaf {
+                key,
+                board,
+                valid,
+                path,
+            }));
+        }
+    }
+
+    fn is_settled(&self, root: &BoardState) -> bool {
+        self.nodes.get(&root.hash()).is_some_and(TreeNode::settled)
+    }
+
+    /// Snapshot for the `onProgress` callback once another `every` simulations have completed.
+    fn progress_report(&self, run: &mut SearchRun, every: u32) -> Option<SearchProgress> {
+        if run.completed / every <= run.reported / every {
+            return None;
         }
+        run.reported = run.completed;
+        let node = self.nodes.get(&run.board.hash())?;
+        let visits: Vec<u32> = node
+            .nsa
+            .iter()
+            .zip(node.valid.iter())
+            .map(|(&count, &flag)| if flag { count } else { 0 })
+            .collect();
+        let best_action = visits
+            .iter()
+            .enumerate()
+            .filter(|(_, &count)| count > 0)
+            .max_by_key(|(_, &count)| count)
+            .map(|(action, _)| action);
+        let green_value = if run.root_player == 0 {
+            node.mean_value
+        } else {
+            -node.mean_value
+        };
+        Some(SearchProgress {
+            simulations: run.completed,
+            best_action,
+            q: [green_value, -green_value],
+            visits,
+        })
     }
 
-    async fn evaluate(
+    /// Expand every evaluated leaf and back its value up the path it was reached by. Leaves of
+    /// an interrupted run are kept if they still lie under the root (below the move `advance`
+    /// played, say); they back up from the root down. The others are dropped and no longer count
+    /// towards the run's completed simulations. A leaf another run expanded in the meantime keeps
+    /// that node and backs up its value instead.
+    fn expand_leaves(
         &mut self,
-        board: &BoardState,
-        valid: &[bool; ACTION_SIZE],
-    ) -> Result<NetworkPrediction, JsValue> {
-        board.write_into_slice(&mut self.board_buffer);
-        for (idx, flag) in valid.iter().enumerate() {
-            self.mask_buffer[idx] = u8::from(*flag);
+        run: &mut SearchRun,
+        leaves: Vec<PendingLeaf>,
+        predictions: Vec<NetworkPrediction>,
+    ) -> Result<(), SearchError> {
+        let interrupted = run.generation != self.generation;
+        let root_key = self.root.map(|root| root.hash());
+        if predictions.len() < leaves.len() {
+            return Err(SearchError::MissingPredictions {
+                expected: leaves.len(),
+                got: predictions.len(),
+            });
+        }
+        if let Some(short) = predictions
+            .iter()
+            .find(|prediction| prediction.pi.len() < ACTION_SIZE)
+        {
+            return Err(SearchError::ShortPolicy {
+                len: short.pi.len(),
+            });
         }
+        for (leaf, prediction) in leaves.iter().zip(&predictions) {
+            let path = if interrupted {
+                // The interrupt already cleared this run's virtual losses.
+                let Some(path) = leaf.path_below(root_key) else {
+                    run.completed = run.completed.saturating_sub(1);
+                    continue;
+                };
+                path
+            } else {
+                self.remove_virtual_loss(&leaf.path);
+                &leaf.path[..]
+            };
+            let leaf_value = self
+                .nodes
+                .entry(leaf.key)
+                .or_insert_with(|| {
+                    TreeNode::from_prediction(leaf.valid, prediction, leaf.board.round())
+                })
+                .mean_value;
+            self.backpropagate(path, leaf_value, None);
+        }
+        Ok(())
+    }
 
-        let value = {
-            let board_array = unsafe { js_sys::Int8Array::view(&self.board_buffer) };
-            let mask_array = unsafe { js_sys::Uint8Array::view(&self.mask_buffer) };
-            let board_js = JsValue::from(board_array);
-            let mask_js = JsValue::from(mask_array);
+    /// Write `leaves` row by row into the board and mask buffers handed to the evaluator.
+    fn stage_leaves(&mut self, leaves: &[PendingLeaf]) {
+        let count = leaves.len();
+        self.board_buffer.resize(count * STATE_SIZE, 0);
+        self.mask_buffer.resize(count * ACTION_SIZE, 0);
+        for (i, leaf) in leaves.iter().enumerate() {
+            leaf.board
+                .write_into_slice(&mut self.board_buffer[i * STATE_SIZE..(i + 1) * STATE_SIZE]);
+            let masks = &mut self.mask_buffer[i * ACTION_SIZE..(i + 1) * ACTION_SIZE];
+            for (slot, flag) in masks.iter_mut().zip(leaf.valid.iter()) {
+                *slot = u8::from(*flag);
+            }
+        }
+    }
 
-            self.predictor
-                .call2(&JsValue::NULL, &board_js, &mask_js)
-                .map_err(|err| JsValue::from(err))?
-        };
-        let promise = js_sys::Promise::from(value);
-        let prediction_value = JsFuture::from(promise).await?;
-        let prediction: NetworkPrediction = serde_wasm_bindgen::from_value(prediction_value)?;
+    /// Mark every edge on `path` as in flight so the rest of the batch steers away from it.
+    fn add_virtual_loss(&mut self, path: &[(u64, usize, bool)]) {
+        for &(key, action, _) in path {
+            let entry = self.in_flight.entry(key).or_insert(InFlight {
+                node: 0,
+                edges: [0; ACTION_SIZE],
+            });
+            entry.node += 1;
+            entry.edges[action] += 1;
+        }
+    }
 
-        if prediction.pi.len() < ACTION_SIZE {
-            return Err(JsValue::from_str(
-                "predictor returned fewer than 162 policy entries",
-            ));
+    fn remove_virtual_loss(&mut self, path: &[(u64, usize, bool)]) {
+        for (key, action, _) in path {
+            if let Some(entry) = self.in_flight.get_mut(key) {
+                entry.node -= 1;
+                entry.edges[*action] -= 1;
+                if entry.node == 0 {
+                    self.in_flight.remove(key);
+                }
+            }
         }
-        Ok(prediction)
     }
 
-    fn backpropagate(&mut self, path: &[([i8; STATE_SIZE], usize, bool)], mut value: f32) {
+    /// Debug builds remember the position behind each key and panic if two different positions
+    /// ever share one; release builds compile this away.
+    #[inline]
+    fn guard_key(&mut self, key: u64, board: &BoardState) {
+        #[cfg(debug_assertions)]
+        {
+            let known = self.key_guard.entry(key).or_insert(*board);
+            assert!(
+                known.same_position(board),
+                "Zobrist collision on key {key:#018x}"
+            );
+        }
+        #[cfg(not(debug_assertions))]
+        let _ = (key, board);
+    }
+
+    /// Back `value` (and `proof`, when the leaf's result is known) up `path`. Proofs stop
+    /// climbing at the first node they do not settle; proven values keep being discounted by
+    /// their distance to the end of the game.
+    fn backpropagate(
+        &mut self,
+        path: &[(u64, usize, bool)],
+        mut value: f32,
+        mut proof: Option<Proof>,
+    ) {
+        let decay = 1.0 - self.config.terminal_discount.clamp(0.0, 1.0);
+        let mut distance = proof.map(|proof| i32::from(proof.plies));
         for (key, action, flipped) in path.iter().rev() {
             if *flipped {
                 value = -value;
+                proof = proof.map(Proof::flipped);
             }
+            distance = distance.map(|plies| plies + 1);
+            let discounted = distance.map_or(value, |plies| value * decay.powi(plies));
             if let Some(node) = self.nodes.get_mut(key) {
-                node.record_value(value);
+                proof = proof.and_then(|child| node.absorb_proof(*action, child));
+                node.record_value(discounted);
 
                 let edge_visits = &mut node.nsa[*action];
                 *edge_visits += 1;
                 let edge_visits_f = *edge_visits as f32;
                 let edge_value = &mut node.qsa[*action];
-                *edge_value += (value - *edge_value) / edge_visits_f;
+                *edge_value += (discounted - *edge_value) / edge_visits_f;
             }
         }
     }
 
-    fn maybe_cleanup(&mut self, current_round: u16) {
-        if self.config.no_mem_optim {
-            return;
-        }
-        if current_round <= self.last_cleanup_round + self.config.cleanup_interval {
-            return;
+    /// Move the root to the canonical position `root`, dropping everything that cannot be reached
+    /// from it unless `no_mem_optim` is set. Returns the visits already spent on `root`.
+    fn reroot(&mut self, root: BoardState) -> u32 {
+        let moved = !self
+            .root
+            .is_some_and(|current| current.same_position(&root));
+        if moved && !self.config.no_mem_optim {
+            prune_to_subtree(&mut self.nodes, &root);
+            #[cfg(debug_assertions)]
+            self.key_guard.retain(|key, _| self.nodes.contains_key(key));
         }
-        let threshold = current_round.saturating_sub(self.config.retain_rounds);
-        self.nodes.retain(|_, node| node.round >= threshold);
-        self.last_cleanup_round = current_round;
+        self.root = Some(root);
+        self.nodes
+            .get(&root.hash())
+            .map_or(0, |node| node.visit_count)
     }
 
     fn root_distribution(
@@ -635,6 +2041,143 @@ impl SantoriniMcts {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::convert::Infallible;
+    use std::sync::Arc;
+    use std::task::{Context, Poll, Wake, Waker};
+
+    use crate::moves::{Move, Square};
+
+    /// Flat prior over the legal moves and a drawish value everywhere.
+    struct Uniform;
+
+    impl Evaluator for Uniform {
+        type Error = Infallible;
+
+        fn evaluate(
+            &mut self,
+            _board: &[i8],
+            valid: &[u8],
+        ) -> Result<NetworkPrediction, Infallible> {
+            Ok(NetworkPrediction {
+                pi: vec![0.0; valid.len()],
+                v: 0.0,
+            })
+        }
+    }
+
+    /// Near-certain prior on one action whenever it is legal, and a fixed value everywhere.
+    struct Favouring {
+        action: usize,
+        value: f32,
+    }
+
+    impl Evaluator for Favouring {
+        type Error = Infallible;
+
+        fn evaluate(
+            &mut self,
+            _board: &[i8],
+            valid: &[u8],
+        ) -> Result<NetworkPrediction, Infallible> {
+            let mut pi = vec![0.0; valid.len()];
+            pi[self.action] = 8.0;
+            Ok(NetworkPrediction { pi, v: self.value })
+        }
+    }
+
+    /// [`Uniform`] answers, valued at the given score, that only arrive on the second poll, like
+    /// a predictor running elsewhere, so tests can reach the engine while a batch is out.
+    struct Deferred(f32);
+
+    struct Later(Option<Vec<NetworkPrediction>>, bool);
+
+    impl Future for Later {
+        type Output = Result<Vec<NetworkPrediction>, Infallible>;
+
+        fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+            if !std::mem::replace(&mut self.1, true) {
+                cx.waker().wake_by_ref();
+                return Poll::Pending;
+            }
+            Poll::Ready(Ok(self.0.take().expect("polled after completion")))
+        }
+    }
+
+    impl AsyncEvaluator for Deferred {
+        type Error = Infallible;
+        type Pending = Later;
+
+        fn request(&mut self, boards: &[i8], valid: &[u8], count: usize) -> Later {
+            let mut predictions = Uniform.evaluate_batch(boards, valid, count).ok();
+            for prediction in predictions.iter_mut().flatten() {
+                prediction.v = self.0;
+            }
+            Later(predictions, false)
+        }
+    }
+
+    struct NoopWake;
+
+    impl Wake for NoopWake {
+        fn wake(self: Arc<Self>) {}
+    }
+
+    fn poll_once<F: Future>(future: Pin<&mut F>) -> Poll<F::Output> {
+        let waker = Waker::from(Arc::new(NoopWake));
+        future.poll(&mut Context::from_waker(&waker))
+    }
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = pin!(future);
+        loop {
+            if let Poll::Ready(output) = poll_once(future.as_mut()) {
+                return output;
+            }
+        }
+    }
+
+    fn deferred(config: MctsConfig) -> RefCell<Mcts<Deferred>> {
+        let mut mcts = Mcts::new(config, Deferred(0.0));
+        mcts.set_seed(11);
+        RefCell::new(mcts)
+    }
+
+    fn seeded(config: MctsConfig) -> Mcts<Uniform> {
+        let mut mcts = Mcts::new(config, Uniform);
+        mcts.set_seed(11);
+        mcts
+    }
+
+    /// All four workers placed, nothing built yet.
+    fn opening_bytes(bytes: &mut [i8; STATE_SIZE]) {
+        let mut board = BoardState::new();
+        for (action, player) in [(6, 0), (12, 0), (8, 1), (18, 1)] {
+            board.make_move(action, player);
+        }
+        board.write_into_slice(bytes);
+    }
+
+    /// Player 0 to move with worker 1 on level 2 next to a level-3 tower; returns the climb.
+    fn immediate_win_bytes(bytes: &mut [i8; STATE_SIZE]) -> usize {
+        let mut board = BoardState::new();
+        for (action, player) in [(6, 0), (18, 0), (8, 1), (16, 1)] {
+            board.make_move(action, player);
+        }
+        board.write_into_slice(bytes);
+        bytes[6 * 3 + 1] = 2;
+        bytes[7 * 3 + 1] = 3;
+        BoardState::from_bytes(bytes)
+            .winning_move(0)
+            .expect("climb onto the tower")
+    }
+
+    fn interior_node() -> TreeNode {
+        let prediction = NetworkPrediction {
+            pi: vec![0.0; ACTION_SIZE],
+            v: 0.0,
+        };
+        TreeNode::from_prediction([false; ACTION_SIZE], &prediction, 0)
+    }
 
     #[test]
     fn record_value_matches_legacy_average() {
@@ -647,6 +2190,7 @@ mod tests {
             mean_value: 0.2,
             terminal_value: None,
             round: 0,
+            solver: Solver::default(),
         };
 
         node.record_value(0.4);
@@ -664,4 +2208,740 @@ mod tests {
         let cfg = MctsConfig::default();
         assert_eq!(cfg.dirichlet_weight, 0.0);
     }
+
+    #[test]
+    fn virtual_loss_steers_selection_away_from_pending_edges() {
+        let mut node = TreeNode {
+            policy: [0.0; ACTION_SIZE],
+            valid: [false; ACTION_SIZE],
+            visit_count: 4,
+            qsa: [0.0; ACTION_SIZE],
+            nsa: [0; ACTION_SIZE],
+            mean_value: 0.0,
+            terminal_value: None,
+            round: 0,
+            solver: Solver::default(),
+        };
+        for (action, q) in [(3, 0.5), (7, 0.4)] {
+            node.valid[action] = true;
+            node.policy[action] = 0.5;
+            node.qsa[action] = q;
+            node.nsa[action] = 2;
+        }
+        let select =
+            |pending: Option<&InFlight>| node.select_action(1.0, 0.0, false, 1, 0.5, pending, 1.0);
+        assert_eq!(select(None), 3);
+
+        let mut in_flight = InFlight {
+            node: 1,
+            edges: [0; ACTION_SIZE],
+        };
+        in_flight.edges[3] = 1;
+        assert_eq!(select(Some(&in_flight)), 7);
+    }
+
+    #[test]
+    fn pruning_keeps_only_the_subtree_under_the_root() {
+        let node = |round: u16| TreeNode::terminal([false; ACTION_SIZE], 0.0, round);
+        let root = BoardState::new();
+        let mut child = root;
+        let next_player = child.make_move(6, 0);
+        let child = child.canonicalised(next_player);
+        let mut unrelated = root;
+        let next_player = unrelated.make_move(7, 0);
+        let unrelated = unrelated.canonicalised(next_player);
+
+        let mut nodes = NodeTable::default();
+        let mut root_node = node(0);
+        root_node.valid[6] = true;
+        root_node.valid[7] = true;
+        root_node.nsa[6] = 3;
+        root_node.visit_count = 3;
+        nodes.insert(root.hash(), root_node);
+        nodes.insert(child.hash(), node(1));
+        // Expanded but never reached through an edge the search took.
+        nodes.insert(unrelated.hash(), node(1));
+
+        prune_to_subtree(&mut nodes, &root);
+        assert_eq!(nodes.len(), 2);
+        assert!(!nodes.contains_key(&unrelated.hash()));
+
+        prune_to_subtree(&mut nodes, &child);
+        assert_eq!(nodes.len(), 1);
+        assert!(nodes.contains_key(&child.hash()));
+    }
+
+    #[test]
+    fn search_budget_respects_bounds_around_the_deadline() {
+        let fixed = SearchBudget::simulations(3);
+        assert!(fixed.allows(2));
+        assert!(!fixed.allows(3));
+
+        let expired = SearchBudget {
+            min_simulations: 2,
+            max_simulations: 10,
+            deadline_ms: Some(clock::now_ms() - 1.0),
+        };
+        assert!(expired.allows(1));
+        assert!(!expired.allows(2));
+
+        let open = SearchBudget {
+            deadline_ms: Some(clock::now_ms() + 60_000.0),
+            ..expired
+        };
+        assert!(open.allows(9));
+        assert!(!open.allows(10));
+    }
+
+    #[test]
+    fn principal_variation_follows_most_visited_edges() {
+        let mut bytes = [0i8; STATE_SIZE];
+        opening_bytes(&mut bytes);
+        let root = BoardState::from_bytes(&bytes);
+        let moves: Vec<usize> = root.legal_moves(0).map(|mv| mv.action()).collect();
+        let (best, second) = (moves[0], moves[1]);
+
+        let mut nodes = NodeTable::default();
+        let mut root_node = interior_node();
+        for (action, visits, q) in [(best, 5, 0.25), (second, 2, -0.5)] {
+            root_node.valid[action] = true;
+            root_node.nsa[action] = visits;
+            root_node.qsa[action] = q;
+        }
+        nodes.insert(root.hash(), root_node);
+
+        let mut child = root;
+        let next_player = child.make_move(best, 0);
+        let child = child.canonicalised(next_player);
+        let reply = child.legal_moves(0).next().expect("reply").action();
+        let mut child_node = interior_node();
+        child_node.valid[reply] = true;
+        child_node.nsa[reply] = 4;
+        nodes.insert(child.hash(), child_node);
+
+        let lines = principal_variations(&nodes, &root, 1, 3);
+        assert_eq!(lines.len(), 2);
+        let steps: Vec<(usize, u8, u32)> = lines[0]
+            .iter()
+            .map(|step| (step.action, step.player, step.visits))
+            .collect();
+        assert_eq!(steps, vec![(best, 1, 5), (reply, 0, 4)]);
+        assert_eq!(
+            lines[1],
+            vec![PvStep {
+                action: second,
+                player: 1,
+                q: -0.5,
+                visits: 2
+            }]
+        );
+    }
+
+    #[test]
+    fn edge_stats_are_sorted_and_match_selection_scores() {
+        let mut node = interior_node();
+        node.visit_count = 6;
+        for (action, prior, visits, q) in [(2, 0.2, 1, -0.5), (9, 0.5, 5, 0.3), (11, 0.3, 0, 0.0)] {
+            node.valid[action] = true;
+            node.policy[action] = prior;
+            node.nsa[action] = visits;
+            node.qsa[action] = q;
+        }
+        let edges = node.edge_stats(1.5, 0.1);
+        let order: Vec<usize> = edges.iter().map(|edge| edge.action).collect();
+        assert_eq!(order, vec![9, 2, 11]);
+        assert_eq!(edges[2].q, None);
+
+        let best = edges
+            .iter()
+            .max_by(|a, b| a.ucb.total_cmp(&b.ucb))
+            .map(|edge| edge.action);
+        let selected = node.select_action(1.5, 0.1, false, 1, 0.5, None, 1.0);
+        assert_eq!(best, Some(selected));
+    }
+
+    #[test]
+    fn solver_proves_wins_and_losses_from_children() {
+        let loss_in = |plies| Proof {
+            won: false,
+            plies,
+            action: None,
+        };
+        let mut node = interior_node();
+        for action in [4, 40, 90] {
+            node.valid[action] = true;
+            node.policy[action] = 1.0 / 3.0;
+        }
+
+        assert_eq!(node.absorb_proof(40, loss_in(2)), None);
+        assert_ne!(node.select_action(1.0, 0.0, false, 1, 0.5, None, 1.0), 40);
+        assert_eq!(node.absorb_proof(4, loss_in(6)), None);
+        assert_eq!(node.select_action(1.0, 0.0, false, 1, 0.5, None, 1.0), 90);
+        assert_eq!(
+            node.absorb_proof(90, loss_in(0)),
+            Some(Proof {
+                won: false,
+                plies: 7,
+                action: Some(4),
+            })
+        );
+
+        let mut node = interior_node();
+        node.valid[12] = true;
+        let won = node.absorb_proof(12, loss_in(0).flipped());
+        assert_eq!(
+            won,
+            Some(Proof {
+                won: true,
+                plies: 1,
+                action: Some(12),
+            })
+        );
+    }
+
+    #[test]
+    fn quicker_wins_replace_slower_proofs() {
+        let win_in = |plies| Proof {
+            won: true,
+            plies,
+            action: None,
+        };
+        let mut node = interior_node();
+        node.valid[3] = true;
+        node.valid[30] = true;
+        node.absorb_proof(3, win_in(4));
+        assert!(!node.settled());
+        node.absorb_proof(30, win_in(0));
+        assert_eq!(
+            node.absorb_proof(3, win_in(2)),
+            Some(Proof {
+                won: true,
+                plies: 1,
+                action: Some(30),
+            })
+        );
+        assert!(node.settled());
+    }
+
+    #[test]
+    fn terminal_discount_prefers_quick_wins_and_slow_losses() {
+        let mut mcts = seeded(MctsConfig {
+            terminal_discount: 0.1,
+            ..MctsConfig::default()
+        });
+        let mut backed_up = |key: u64, won: bool, plies: u16| {
+            let mut node = interior_node();
+            node.valid[0] = true;
+            mcts.nodes.insert(key, node);
+            let proof = Proof {
+                won,
+                plies,
+                action: None,
+            };
+            mcts.backpropagate(&[(key, 0, false)], proof.value(), Some(proof));
+            mcts.nodes[&key].qsa[0]
+        };
+
+        // One ply to reach the proven child, then `plies` more to the end of the game.
+        let (quick_win, slow_win) = (backed_up(1, true, 0), backed_up(2, true, 2));
+        assert!((quick_win - 0.9).abs() < 1e-6);
+        assert!((slow_win - 0.729).abs() < 1e-6);
+        assert!(quick_win > slow_win);
+        let (fast_loss, slow_loss) = (backed_up(3, false, 0), backed_up(4, false, 2));
+        assert!((fast_loss + 0.9).abs() < 1e-6);
+        assert!((slow_loss + 0.729).abs() < 1e-6);
+        assert!(slow_loss > fast_loss);
+    }
+
+    #[test]
+    fn leader_margin_ignores_invalid_edges() {
+        let mut node = interior_node();
+        for (action, visits) in [(5, 30), (6, 12), (7, 41)] {
+            node.valid[action] = true;
+            node.nsa[action] = visits;
+        }
+        node.nsa[8] = 100;
+        assert_eq!(node.leader_margin(), 11);
+    }
+
+    #[test]
+    fn native_search_plays_the_immediate_win() {
+        let mut bytes = [0i8; STATE_SIZE];
+        let win = immediate_win_bytes(&mut bytes);
+
+        let mut mcts = seeded(MctsConfig {
+            num_simulations: 32,
+            ..MctsConfig::default()
+        });
+        let result = mcts.search(&bytes, 0, 0.0, true).expect("search");
+        assert_eq!(result.policy[win], 1.0);
+        assert!(result
+            .proof
+            .is_some_and(|proof| proof.won && proof.plies == 1));
+    }
+
+    #[test]
+    fn gumbel_search_plays_the_win_and_returns_the_improved_policy() {
+        let config = MctsConfig {
+            num_simulations: 64,
+            root_policy: RootPolicy::Gumbel,
+            ..MctsConfig::default()
+        };
+        let mut bytes = [0i8; STATE_SIZE];
+        let win = immediate_win_bytes(&mut bytes);
+        let mut mcts = seeded(config.clone());
+        let result = mcts.search(&bytes, 0, 0.0, true).expect("search");
+        assert_eq!(result.policy[win], 1.0);
+
+        opening_bytes(&mut bytes);
+        let mut mcts = Mcts::new(config, HeuristicEvaluator::default());
+        mcts.set_seed(11);
+        let result = mcts.search(&bytes, 0, 1.0, true).expect("search");
+        assert_eq!(result.simulations, 64);
+        let root = &mcts.nodes[&mcts.root.expect("root").hash()];
+        let mut playable = root.valid;
+        for (action, flag) in playable.iter_mut().enumerate() {
+            *flag &= !root.solver.is_lost(action);
+        }
+        let expected = improved_policy(
+            &root.policy,
+            &playable,
+            &root.nsa,
+            &root.qsa,
+            root.mean_value,
+            1.0,
+            mcts.q_transform(),
+        );
+        for (share, expected) in result.policy.iter().zip(&expected) {
+            assert!((share - expected).abs() < 1e-6);
+        }
+        assert!((result.policy.iter().sum::<f32>() - 1.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn abort_before_the_root_returns_the_uniform_policy() {
+        let mut mcts = seeded(MctsConfig::default());
+        let mut bytes = [0i8; STATE_SIZE];
+        BoardState::new().write_into_slice(&mut bytes);
+
+        mcts.abort.set(true);
+        let result = mcts.search(&bytes, 0, 0.0, true).expect("search");
+        assert!(result.aborted);
+        assert_eq!(result.simulations, 0);
+        // Every one of the 25 placements is legal in the opening.
+        assert!(result.policy[..25].iter().all(|&share| share == 1.0 / 25.0));
+        assert!(!mcts.abort.get());
+
+        // Failed searches consume the abort too.
+        mcts.abort.set(true);
+        assert!(mcts.search(&bytes[1..], 0, 0.0, true).is_err());
+        assert!(!mcts.abort.get());
+    }
+
+    #[test]
+    fn early_stop_ends_once_the_leader_cannot_be_caught() {
+        let mut bytes = [0i8; STATE_SIZE];
+        BoardState::new().write_into_slice(&mut bytes);
+        let config = MctsConfig {
+            num_simulations: 200,
+            early_stop: true,
+            ..MctsConfig::default()
+        };
+        let mut mcts = Mcts::new(
+            config,
+            Favouring {
+                action: 12,
+                value: 0.0,
+            },
+        );
+        mcts.set_seed(11);
+
+        let result = mcts.search(&bytes, 0, 0.0, true).expect("search");
+        assert_eq!(result.policy[12], 1.0);
+        assert!(result.simulations < 200);
+        assert!(result.simulations_saved > 0);
+        assert_eq!(result.simulations + result.simulations_saved, 200);
+    }
+
+    #[test]
+    fn forced_moves_only_evaluate_the_root() {
+        let mut bytes = [0i8; STATE_SIZE];
+        for (square, worker) in [(0, 1), (24, 2), (20, -1), (4, -2)] {
+            bytes[square * 3] = worker;
+        }
+        // Domes leave worker 1 a single step to square 6 and a single build back on square 0.
+        for square in [1, 2, 5, 7, 10, 11, 12, 18, 19, 23] {
+            bytes[square * 3 + 1] = 4;
+        }
+        let board = BoardState::from_bytes(&bytes);
+        let forced: Vec<Move> = board.legal_moves(0).collect();
+        assert_eq!(forced.len(), 1);
+
+        let config = MctsConfig {
+            num_simulations: 100,
+            early_stop: true,
+            ..MctsConfig::default()
+        };
+        let mut mcts = Mcts::new(
+            config,
+            Favouring {
+                action: 0,
+                value: 0.5,
+            },
+        );
+        let result = mcts.search(&bytes, 0, 1.0, true).expect("search");
+        assert_eq!(result.policy[forced[0].action()], 1.0);
+        assert_eq!(result.q, [0.5, -0.5]);
+        assert_eq!((result.simulations, result.simulations_saved), (1, 99));
+    }
+
+    #[test]
+    fn batched_search_backs_up_every_simulation() {
+        let mut bytes = [0i8; STATE_SIZE];
+        opening_bytes(&mut bytes);
+
+        let mut mcts = seeded(MctsConfig {
+            num_simulations: 200,
+            batch_size: 8,
+            ..MctsConfig::default()
+        });
+        let result = mcts.search(&bytes, 0, 1.0, true).expect("search");
+        assert_eq!(result.simulations, 200);
+        // Every simulation but the one expanding the root went through a root edge.
+        assert_eq!(result.visits.iter().sum::<u32>(), result.simulations - 1);
+        let root = &mcts.nodes[&mcts.root.expect("root").hash()];
+        assert_eq!(root.visit_count, result.simulations - 1);
+        assert!(mcts.in_flight.is_empty());
+    }
+
+    #[test]
+    fn native_search_reuses_the_subtree_after_advance() {
+        let mut bytes = [0i8; STATE_SIZE];
+        opening_bytes(&mut bytes);
+
+        let mut mcts = seeded(MctsConfig {
+            num_simulations: 200,
+            ..MctsConfig::default()
+        });
+        let result = mcts.search(&bytes, 0, 0.0, true).expect("search");
+        assert_eq!(result.simulations, 200);
+        let best = result.edges[0];
+        assert_eq!(mcts.advance(best.action).expect("legal"), best.visits - 1);
+        assert!((mcts.nodes.len() as u32) < result.transpositions.nodes);
+    }
+
+    #[test]
+    fn set_root_keeps_a_known_subtree_and_rejects_bad_boards() {
+        let mut bytes = [0i8; STATE_SIZE];
+        opening_bytes(&mut bytes);
+        let mut mcts = seeded(MctsConfig {
+            num_simulations: 200,
+            ..MctsConfig::default()
+        });
+        let result = mcts.search(&bytes, 0, 0.0, true).expect("search");
+        let best = result.edges[0];
+
+        let mut child = BoardState::from_bytes(&bytes);
+        let next_player = child.make_move(best.action, 0);
+        let key = child.canonicalised(next_player).hash();
+        let subtree = mcts.nodes[&key].visit_count;
+        assert_eq!(subtree, best.visits - 1);
+        let nodes_before = mcts.nodes.len();
+        assert_eq!(
+            mcts.set_root(&child.to_vec(), next_player as u8),
+            Ok(subtree)
+        );
+        assert!(mcts.nodes.len() < nodes_before);
+        assert_eq!(mcts.nodes[&key].visit_count, subtree);
+
+        // Rejected boards, here a dome under the untouched worker on square 8, leave the root
+        // and its tree alone.
+        let nodes_kept = mcts.nodes.len();
+        let mut domed = child.to_vec();
+        domed[8 * 3 + 1] = 4;
+        assert!(matches!(
+            mcts.set_root(&domed, next_player as u8),
+            Err(SantoriniError::InvalidBoard { .. })
+        ));
+        assert_eq!(
+            mcts.set_root(&bytes[1..], 0),
+            Err(SantoriniError::InvalidStateLength {
+                len: STATE_SIZE - 1
+            })
+        );
+        assert_eq!(mcts.root.map(|root| root.hash()), Some(key));
+        assert_eq!(mcts.nodes.len(), nodes_kept);
+    }
+
+    #[test]
+    fn a_second_move_order_reaches_the_same_node() {
+        let mut bytes = [0i8; STATE_SIZE];
+        opening_bytes(&mut bytes);
+        let opening = BoardState::from_bytes(&bytes);
+        let step = |board: &BoardState, player: usize, squares: (Square, Square, Square)| {
+            board
+                .legal_moves(player)
+                .find(|mv| match *mv {
+                    Move::Step {
+                        from, to, build, ..
+                    } => (from, to, build) == squares,
+                    Move::Placement { .. } => false,
+                })
+                .expect("legal step")
+                .action()
+        };
+        // Two independent moves by player 0 around one by player 1, played in either order.
+        let corner = step(&opening, 0, ((1, 1), (0, 0), (0, 1)));
+        let centre = step(&opening, 0, ((2, 2), (3, 1), (4, 0)));
+        let reply = step(&opening, 1, ((1, 3), (0, 4), (0, 3)));
+        let mut first = opening;
+        first.make_move(corner, 0);
+        first.make_move(reply, 1);
+        let mut second = opening;
+        second.make_move(centre, 0);
+        second.make_move(reply, 1);
+        let (mut joined, mut rejoined) = (first, second);
+        joined.make_move(centre, 0);
+        rejoined.make_move(corner, 0);
+        assert_eq!(joined.hash(), rejoined.hash());
+
+        let mut mcts = Mcts::new(
+            MctsConfig {
+                num_simulations: 2,
+                no_mem_optim: true,
+                ..MctsConfig::default()
+            },
+            Favouring {
+                action: centre,
+                value: 0.0,
+            },
+        );
+        mcts.set_seed(11);
+        // Expand the root, then follow the favoured move into the joined position.
+        let result = mcts.search(&first.to_vec(), 0, 0.0, true).expect("search");
+        assert_eq!(
+            (result.transpositions.probes, result.transpositions.hits),
+            (3, 1)
+        );
+
+        mcts.evaluator.action = corner;
+        let result = mcts.search(&second.to_vec(), 0, 0.0, true).expect("search");
+        // The root's second lookup hits as before; the joined position is the extra hit.
+        assert_eq!(
+            (result.transpositions.probes, result.transpositions.hits),
+            (4, 2)
+        );
+        assert_eq!(result.transpositions.nodes, 4);
+    }
+
+    #[test]
+    fn stop_ends_the_ponder_and_keeps_its_tree() {
+        let mut bytes = [0i8; STATE_SIZE];
+        opening_bytes(&mut bytes);
+        let engine = deferred(MctsConfig {
+            batch_size: 4,
+            ..MctsConfig::default()
+        });
+        let mut ponder = pin!(Mcts::ponder(&engine, &bytes, 1, None));
+        for _ in 0..40 {
+            assert!(poll_once(ponder.as_mut()).is_pending());
+        }
+        engine.borrow_mut().stop();
+        let Poll::Ready(Ok(completed)) = poll_once(ponder.as_mut()) else {
+            panic!("stop() should end the ponder");
+        };
+
+        // The batch that was out when stop() landed still made it into the tree.
+        let mcts = engine.borrow();
+        let root = &mcts.nodes[&mcts.root.expect("root").hash()];
+        assert_eq!(root.visit_count + 1, completed);
+        assert!(completed > 40);
+        assert!(mcts.in_flight.is_empty());
+    }
+
+    #[test]
+    fn advance_hands_the_pondered_subtree_to_the_reply() {
+        let mut bytes = [0i8; STATE_SIZE];
+        opening_bytes(&mut bytes);
+        let engine = deferred(MctsConfig {
+            batch_size: 4,
+            ..MctsConfig::default()
+        });
+        let mut ponder = pin!(Mcts::ponder(&engine, &bytes, 1, None));
+        for _ in 0..60 {
+            assert!(poll_once(ponder.as_mut()).is_pending());
+        }
+
+        let (action, visits_before) = {
+            let mcts = engine.borrow();
+            let root = &mcts.nodes[&mcts.root.expect("root").hash()];
+            let action = (0..ACTION_SIZE)
+                .max_by_key(|&action| root.nsa[action])
+                .expect("actions");
+            (action, root.visit_count)
+        };
+        let carried = engine.borrow_mut().advance(action).expect("legal");
+        assert!(carried > 0);
+        let Poll::Ready(Ok(completed)) = poll_once(ponder.as_mut()) else {
+            panic!("advance() should end the ponder");
+        };
+
+        // Only the leaves of the last batch that lie below `action` landed, and only they
+        // still count as completed.
+        let mcts = engine.borrow();
+        let root = &mcts.nodes[&mcts.root.expect("root").hash()];
+        let landed = root.visit_count - carried;
+        assert_eq!(completed, visits_before + 1 + landed);
+    }
+
+    #[test]
+    fn a_search_interrupts_a_running_ponder() {
+        let mut bytes = [0i8; STATE_SIZE];
+        opening_bytes(&mut bytes);
+        let engine = deferred(MctsConfig {
+            num_simulations: 64,
+            batch_size: 4,
+            ..MctsConfig::default()
+        });
+        let mut ponder = pin!(Mcts::ponder(&engine, &bytes, 1, None));
+        for _ in 0..20 {
+            assert!(poll_once(ponder.as_mut()).is_pending());
+        }
+
+        let (full_search, budget) = engine.borrow_mut().plan_search(true);
+        let result = block_on(Mcts::search_reporting(
+            &engine,
+            &bytes,
+            1,
+            0.0,
+            full_search,
+            budget,
+            |_| Ok::<(), SearchError>(()),
+        ))
+        .expect("search");
+        assert!(!result.aborted);
+        assert_eq!(result.simulations, 64);
+        assert!(matches!(poll_once(ponder.as_mut()), Poll::Ready(Ok(_))));
+    }
+
+    #[test]
+    fn a_leaf_the_interrupted_ponder_expanded_first_keeps_its_node() {
+        let mut bytes = [0i8; STATE_SIZE];
+        opening_bytes(&mut bytes);
+        let engine = deferred(MctsConfig {
+            num_simulations: 4,
+            batch_size: 4,
+            ..MctsConfig::default()
+        });
+        // The first poll evaluates the root, the second sends four of its children out.
+        let mut ponder = pin!(Mcts::ponder(&engine, &bytes, 0, None));
+        for _ in 0..2 {
+            assert!(poll_once(ponder.as_mut()).is_pending());
+        }
+
+        // The search sends the same four children out again, valued differently.
+        engine.borrow_mut().evaluator.0 = 0.5;
+        let mut search = pin!(Mcts::search_async(&engine, &bytes, 0, 1.0, true));
+        assert!(poll_once(search.as_mut()).is_pending());
+        assert!(matches!(poll_once(ponder.as_mut()), Poll::Ready(Ok(5))));
+        let Poll::Ready(Ok(result)) = poll_once(search.as_mut()) else {
+            panic!("the search's only batch should have landed");
+        };
+        assert_eq!(result.simulations, 4);
+
+        // Both runs backed up through the ponder's nodes.
+        let mcts = engine.borrow();
+        let root = mcts.root.expect("root");
+        let root_node = &mcts.nodes[&root.hash()];
+        assert_eq!(root_node.visit_count, 8);
+        let reached: Vec<usize> = (0..ACTION_SIZE)
+            .filter(|&action| root_node.nsa[action] > 0)
+            .collect();
+        assert_eq!(reached.len(), 4);
+        for action in reached {
+            assert_eq!(root_node.nsa[action], 2);
+            let mut child = root;
+            let next_player = child.make_move(action, 0);
+            let child = child.canonicalised(next_player);
+            assert_eq!(mcts.nodes[&child.hash()].mean_value, 0.0);
+        }
+    }
+
+    #[test]
+    fn async_search_runs_its_budget_and_honours_the_abort_handle() {
+        let mut bytes = [0i8; STATE_SIZE];
+        opening_bytes(&mut bytes);
+        let engine = deferred(MctsConfig {
+            num_simulations: 48,
+            batch_size: 4,
+            ..MctsConfig::default()
+        });
+        let result = block_on(Mcts::search_async(&engine, &bytes, 0, 1.0, true)).expect("search");
+        assert_eq!(result.simulations, 48);
+        assert_eq!(result.visits.iter().sum::<u32>(), 47);
+
+        let abort = engine.borrow().abort_handle();
+        let mut search = pin!(Mcts::search_async(&engine, &bytes, 0, 1.0, true));
+        assert!(poll_once(search.as_mut()).is_pending());
+        abort.abort();
+        let Poll::Ready(Ok(result)) = poll_once(search.as_mut()) else {
+            panic!("the abort should end the search after the batch in flight");
+        };
+        assert!(result.aborted && result.simulations < 48);
+        assert!(!abort.aborted());
+    }
+
+    #[test]
+    fn timed_search_meets_its_minimum_and_stops_at_the_deadline() {
+        let mut bytes = [0i8; STATE_SIZE];
+        opening_bytes(&mut bytes);
+        let mut mcts = Mcts::new(MctsConfig::default(), HeuristicEvaluator::default());
+        mcts.set_seed(11);
+
+        // An expired deadline still runs the minimum, and nothing past it.
+        let result = mcts
+            .search_for(&bytes, 0, 0.0, 1.0, Some(50), None)
+            .expect("search");
+        assert_eq!(result.simulations, 50);
+
+        // A cap the deadline reaches long before the search could.
+        let started = std::time::Instant::now();
+        let result = mcts
+            .search_for(&bytes, 0, 40.0, 1.0, Some(10), Some(1_000_000))
+            .expect("search");
+        let elapsed = started.elapsed().as_secs_f64() * 1000.0;
+        assert!((40.0..1000.0).contains(&elapsed), "took {elapsed} ms");
+        assert!(result.simulations > 10 && result.simulations < 1_000_000);
+        assert!(!result.aborted);
+    }
+
+    #[test]
+    fn heuristic_search_blocks_the_opponents_win() {
+        let mut bytes = [0i8; STATE_SIZE];
+        for (square, worker) in [(6, 1), (0, 2), (17, -1), (24, -2)] {
+            bytes[square * 3] = worker;
+        }
+        // Worker -1 on level 2 next to the level-3 square 12: everything but a dome there loses.
+        bytes[17 * 3 + 1] = 2;
+        bytes[12 * 3 + 1] = 3;
+
+        let mut mcts = Mcts::new(
+            MctsConfig {
+                num_simulations: 200,
+                ..MctsConfig::default()
+            },
+            HeuristicEvaluator::default(),
+        );
+        mcts.set_seed(11);
+        let result = mcts.search(&bytes, 0, 0.0, true).expect("search");
+        assert_eq!(result.simulations, 200);
+        let action = (0..ACTION_SIZE)
+            .find(|&action| result.policy[action] == 1.0)
+            .expect("a chosen move");
+        let board = BoardState::from_bytes(&bytes);
+        assert!(matches!(
+            board.describe_action(action, 0),
+            Ok(Move::Step { build: (2, 2), .. })
+        ));
+    }
 }
@@ -0,0 +1,248 @@
+use serde::Serialize;
+
+use crate::board::{encode_action, BoardState, BOARD_SIZE, CELL_COUNT};
+use crate::tables::{step, NO_BUILD, NO_MOVE};
+
+/// Board coordinate as `(y, x)`, serialised to JavaScript as a `[y, x]` pair.
+pub type Square = (usize, usize);
+
+/// A legal action with every square it touches spelled out, so callers never need to re-run
+/// `decode_action`/`apply_direction` themselves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum Move {
+    /// Setup phase: drop the next worker on `square`.
+    Placement { action: usize, square: Square },
+    /// Regular turn: move `worker` (0 or 1) from `from` to `to`, then build on `build`.
+    Step {
+        action: usize,
+        worker: usize,
+        from: Square,
+        to: Square,
+        build: Square,
+    },
+}
+
+impl Move {
+    /// Index in the 162-entry action space.
+    pub fn action(&self) -> usize {
+        match *self {
+            Move::Placement { action, .. } | Move::Step { action, .. } => action,
+        }
+    }
+}
+
+#[inline]
+const fn to_square(index: usize) -> Square {
+    (index / BOARD_SIZE, index % BOARD_SIZE)
+}
+
+/// Lazy iterator over the legal moves of a position, yielded in ascending action order.
+pub struct LegalMoves<'a> {
+    board: &'a BoardState,
+    placing: bool,
+    player_sign: i8,
+    workers: [Option<usize>; 2],
+    square: usize,
+    worker: usize,
+    move_direction: usize,
+    build_direction: usize,
+    target: Option<usize>,
+}
+
+impl<'a> LegalMoves<'a> {
+    pub(crate) fn new(board: &'a BoardState, player: usize) -> Self {
+        let player_sign = if player == 0 { 1 } else { -1 };
+        let placing = board.next_placement().is_some();
+        let workers = if placing {
+            [None; 2]
+        } else {
+            [
+                board.worker_square(player_sign),
+                board.worker_square(2 * player_sign),
+            ]
+        };
+        Self {
+            board,
+            placing,
+            player_sign,
+            workers,
+            square: 0,
+            worker: 0,
+            move_direction: 0,
+            build_direction: 0,
+            target: None,
+        }
+    }
+
+    fn next_placement(&mut self) -> Option<Move> {
+        while self.square < CELL_COUNT {
+            let square = self.square;
+            self.square += 1;
+            if self.board.worker_at(square) == 0 {
+                return Some(Move::Placement {
+                    action: square,
+                    square: to_square(square),
+                });
+            }
+        }
+        None
+    }
+
+    /// Advance to the next `(worker, move_direction)` pair whose move is legal.
+    fn advance_target(&mut self) -> bool {
+        while self.worker < 2 {
+            if let Some(from) = self.workers[self.worker] {
+                while self.move_direction < 9 {
+                    let direction = self.move_direction;
+                    self.move_direction += 1;
+                    if direction == NO_MOVE {
+                        continue;
+                    }
+                    let Some(to) = step(from, direction) else {
+                        continue;
+                    };
+                    if self.board.can_move(from, to) {
+                        self.target = Some(to);
+                        self.build_direction = 0;
+                        return true;
+                    }
+                }
+            }
+            self.worker += 1;
+            self.move_direction = 0;
+        }
+        false
+    }
+}
+
+impl Iterator for LegalMoves<'_> {
+    type Item = Move;
+
+    fn next(&mut self) -> Option<Move> {
+        if self.placing {
+            return self.next_placement();
+        }
+        loop {
+            let Some(to) = self.target else {
+                if !self.advance_target() {
+                    return None;
+                }
+                continue;
+            };
+            while self.build_direction < 9 {
+                let direction = self.build_direction;
+                self.build_direction += 1;
+                if direction == NO_BUILD {
+                    continue;
+                }
+                let Some(build) = step(to, direction) else {
+                    continue;
+                };
+                let worker_id = (self.worker as i8 + 1) * self.player_sign;
+                if !self.board.can_build(build, worker_id) {
+                    continue;
+                }
+                let from = self.workers[self.worker]?;
+                return Some(Move::Step {
+                    action: encode_action(self.worker, self.move_direction - 1, direction),
+                    worker: self.worker,
+                    from: to_square(from),
+                    to: to_square(to),
+                    build: to_square(build),
+                });
+            }
+            self.target = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::{ACTION_SIZE, STATE_SIZE};
+    use crate::error::SantoriniError;
+
+    #[test]
+    fn legal_moves_match_valid_mask() {
+        let mut board = BoardState::new();
+        for (action, player) in [(6, 0), (12, 0), (7, 1), (18, 1)] {
+            board.make_move(action, player);
+        }
+        let moves: Vec<Move> = board.legal_moves(0).collect();
+        let mut mask = [false; ACTION_SIZE];
+        board.valid_moves(0, &mut mask);
+        let expected: Vec<usize> = (0..ACTION_SIZE).filter(|&a| mask[a]).collect();
+        assert_eq!(moves.iter().map(Move::action).collect::<Vec<_>>(), expected);
+
+        let Some(Move::Step {
+            worker,
+            from,
+            to,
+            build,
+            ..
+        }) = moves.first().copied()
+        else {
+            panic!("expected a movement-phase move");
+        };
+        assert_eq!((worker, from, to, build), (0, (1, 1), (0, 0), (0, 1)));
+    }
+
+    #[test]
+    fn coordinates_roundtrip_through_actions() {
+        let mut board = BoardState::new();
+        assert_eq!(board.action_from_coords(0, (1, 1), None, None), Ok(6));
+        assert!(board.action_from_coords(0, (5, 0), None, None).is_err());
+        assert!(board.action_from_coords(1, (1, 1), None, None).is_err());
+        for (action, player) in [(6, 0), (12, 0), (7, 1), (18, 1)] {
+            board.make_move(action, player);
+        }
+
+        let action = board
+            .action_from_coords(0, (1, 1), Some((0, 0)), Some((0, 1)))
+            .expect("legal move");
+        assert_eq!(
+            board.describe_action(action, 0),
+            Ok(Move::Step {
+                action,
+                worker: 0,
+                from: (1, 1),
+                to: (0, 0),
+                build: (0, 1),
+            })
+        );
+        assert_eq!(
+            board.action_from_coords(0, (1, 1), Some((1, 2)), Some((1, 3))),
+            Err(SantoriniError::IllegalCoordinates {
+                from: (1, 1),
+                to: Some((1, 2)),
+                build: Some((1, 3)),
+            })
+        );
+        // Two squares away: not adjacent, so no direction encodes it.
+        assert!(board
+            .action_from_coords(0, (1, 1), Some((3, 1)), Some((3, 2)))
+            .is_err());
+    }
+
+    #[test]
+    fn staged_queries_follow_legal_moves() {
+        let mut bytes = [0i8; STATE_SIZE];
+        for (square, worker) in [(0, 1), (12, 2), (1, -1), (5, -2)] {
+            bytes[square * 3] = worker;
+        }
+        // Worker 1 in the corner is boxed in by both opponents and a level-2 tower.
+        bytes[6 * 3 + 1] = 2;
+        let board = BoardState::from_bytes(&bytes);
+        assert_eq!(board.movable_workers(0), vec![(2, 2)]);
+        assert!(board.legal_destinations(0, (0, 0)).is_empty());
+
+        let destinations = board.legal_destinations(0, (2, 2));
+        assert_eq!(destinations.len(), 7);
+        assert!(!destinations.contains(&(1, 1)));
+        let builds = board.legal_builds(0, (2, 2), (3, 3));
+        assert_eq!(builds.len(), 8);
+        assert!(builds.contains(&(2, 2)));
+        assert!(board.legal_destinations(1, (2, 2)).is_empty());
+    }
+}
@@ -0,0 +1,148 @@
+//! `model_no_god.onnx` evaluated in Rust through tract, behind the `onnx` feature. The model is
+//! fed what ONNX Runtime Web gets from `santoriniRuntime.ts`, stacked over a batch of `N`
+//! boards: the 75-byte boards as a `float32 [N, 25, 3]` tensor named `board` and the legal-action
+//! masks as `bool [N, 162]` named `valid_actions`. Each row of its `pi` output is used as is and
+//! the first entry of each row of `v` is the value of the side to move on that board.
+
+use tract_onnx::prelude::tract_data::anyhow::ensure;
+use tract_onnx::prelude::*;
+
+use crate::board::{ACTION_SIZE, CELL_COUNT, CHANNELS, STATE_SIZE};
+use crate::evaluator::Evaluator;
+use crate::predictor::NetworkPrediction;
+
+type Plan = SimplePlan<TypedFact, Box<dyn TypedOp>, TypedModel>;
+
+/// The Santorini network run on the CPU, a whole batch of boards per call.
+pub struct OnnxEvaluator {
+    plan: Plan,
+}
+
+impl OnnxEvaluator {
+    /// Load and optimise a model from the bytes of its `.onnx` file.
+    pub fn from_bytes(model: &[u8]) -> TractResult<Self> {
+        let model = tract_onnx::onnx().model_for_read(&mut std::io::Cursor::new(model))?;
+        let batch = model.symbol_table.sym("N").to_dim();
+        let plan = model
+            .with_input_names(["board", "valid_actions"])?
+            .with_output_names(["pi", "v"])?
+            .with_input_fact(
+                0,
+                f32::fact([batch.clone(), CELL_COUNT.to_dim(), CHANNELS.to_dim()]).into(),
+            )?
+            .with_input_fact(1, bool::fact([batch, ACTION_SIZE.to_dim()]).into())?
+            // The file declares a symbolic batch on its outputs; let them follow the inputs.
+            .with_output_fact(0, InferenceFact::default())?
+            .with_output_fact(1, InferenceFact::default())?
+            .into_optimized()?
+            .into_runnable()?;
+        Ok(Self { plan })
+    }
+}
+
+impl Evaluator for OnnxEvaluator {
+    type Error = TractError;
+
+    fn evaluate(&mut self, board: &[i8], valid: &[u8]) -> TractResult<NetworkPrediction> {
+        let mut predictions = self.evaluate_batch(board, valid, 1)?;
+        Ok(predictions.remove(0))
+    }
+
+    fn evaluate_batch(
+        &mut self,
+        boards: &[i8],
+        valid: &[u8],
+        count: usize,
+    ) -> TractResult<Vec<NetworkPrediction>> {
+        ensure!(count > 0, "cannot evaluate an empty batch");
+        ensure!(
+            boards.len() >= count * STATE_SIZE && valid.len() >= count * ACTION_SIZE,
+            "expected {count} boards and masks, got {} board and {} mask entries",
+            boards.len(),
+            valid.len()
+        );
+        let boards = tract_ndarray::Array3::from_shape_fn(
+            (count, CELL_COUNT, CHANNELS),
+            |(row, square, channel)| {
+                f32::from(boards[row * STATE_SIZE + square * CHANNELS + channel])
+            },
+        );
+        let valid = tract_ndarray::Array2::from_shape_fn((count, ACTION_SIZE), |(row, action)| {
+            valid[row * ACTION_SIZE + action] != 0
+        });
+        let outputs = self.plan.run(tvec!(
+            boards.into_tensor().into(),
+            valid.into_tensor().into()
+        ))?;
+        let pi = outputs[0].as_slice::<f32>()?;
+        let v = outputs[1].as_slice::<f32>()?;
+        ensure!(
+            pi.len() == count * ACTION_SIZE && !v.is_empty() && v.len() % count == 0,
+            "model returned {} policy entries and {} values for {count} boards",
+            pi.len(),
+            v.len()
+        );
+        Ok(pi
+            .chunks_exact(ACTION_SIZE)
+            .zip(v.chunks_exact(v.len() / count))
+            .map(|(pi, v)| NetworkPrediction {
+                pi: pi.to_vec(),
+                v: v[0],
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::BoardState;
+
+    const MODEL: &str = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../web/src/assets/santorini/model_no_god.onnx"
+    );
+
+    #[test]
+    fn bundled_model_scores_the_opening() {
+        // The model is a separate download; nothing to check without it.
+        let Ok(bytes) = std::fs::read(MODEL) else {
+            return;
+        };
+        let mut evaluator = OnnxEvaluator::from_bytes(&bytes).expect("model loads");
+
+        let board = BoardState::new();
+        let mut bytes = [0i8; STATE_SIZE];
+        board.write_into_slice(&mut bytes);
+        let mut valid = [false; ACTION_SIZE];
+        board.valid_moves(0, &mut valid);
+        let mask: Vec<u8> = valid.iter().map(|&flag| u8::from(flag)).collect();
+
+        let prediction = evaluator.evaluate(&bytes, &mask).expect("inference");
+        assert_eq!(prediction.pi.len(), ACTION_SIZE);
+        // Log-probabilities over the legal moves; illegal ones are masked to -inf.
+        let mass: f32 = (0..ACTION_SIZE)
+            .filter(|&action| valid[action])
+            .map(|action| prediction.pi[action].exp())
+            .sum();
+        assert!((mass - 1.0).abs() < 1e-3);
+        assert!((-1.0..=1.0).contains(&prediction.v));
+
+        // A batch scores every row like a lone board, and short input is an error.
+        let boards = [bytes, bytes].concat();
+        let masks = [mask.clone(), mask.clone()].concat();
+        let batch = evaluator
+            .evaluate_batch(&boards, &masks, 2)
+            .expect("batched inference");
+        assert_eq!(batch.len(), 2);
+        for row in &batch {
+            assert!((row.v - prediction.v).abs() < 1e-5);
+            assert!(row
+                .pi
+                .iter()
+                .zip(&prediction.pi)
+                .all(|(a, b)| a == b || (a - b).abs() < 1e-4));
+        }
+        assert!(evaluator.evaluate(&bytes[..10], &mask).is_err());
+    }
+}
@@ -0,0 +1,137 @@
+//! Move-path enumeration ("perft") used to pin move generation against known node counts.
+//!
+//! Finished games are leaves: a position where [`BoardState::result_value`] reports a winner is not
+//! expanded further, so it contributes `1` at depth 0 and `0` at any greater depth.
+
+use crate::board::{BoardState, ACTION_SIZE};
+
+impl BoardState {
+    /// Number of distinct move sequences of length `depth` starting with `player` to move.
+    pub fn perft(&self, player: usize, depth: u32) -> u64 {
+        let mut board = *self;
+        board.perft_in_place(player, depth)
+    }
+
+    /// [`BoardState::perft`] split by first action, in ascending action order. Handy for
+    /// bisecting a mismatch against a reference implementation.
+    pub fn divide(&self, player: usize, depth: u32) -> Vec<(usize, u64)> {
+        if depth == 0 || self.result_value(player).is_some() {
+            return Vec::new();
+        }
+        let mut board = *self;
+        self.legal_actions(player)
+            .into_iter()
+            .map(|action| {
+                let (next_player, undo) = board
+                    .try_make_move_undoable(action, player)
+                    .expect("generated moves are legal");
+                let nodes = board.perft_in_place(next_player, depth - 1);
+                board.unmake_move(undo);
+                (action, nodes)
+            })
+            .collect()
+    }
+
+    fn perft_in_place(&mut self, player: usize, depth: u32) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+        if self.result_value(player).is_some() {
+            return 0;
+        }
+        let actions = self.legal_actions(player);
+        if depth == 1 {
+            return actions.len() as u64;
+        }
+        let mut nodes = 0;
+        for action in actions {
+            let (next_player, undo) = self
+                .try_make_move_undoable(action, player)
+                .expect("generated moves are legal");
+            nodes += self.perft_in_place(next_player, depth - 1);
+            self.unmake_move(undo);
+        }
+        nodes
+    }
+
+    fn legal_actions(&self, player: usize) -> Vec<usize> {
+        let mut mask = [false; ACTION_SIZE];
+        self.valid_moves(player, &mut mask);
+        (0..ACTION_SIZE).filter(|&action| mask[action]).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::STATE_SIZE;
+
+    // Reference counts were produced by running the same walk over `Board` from
+    // `web/src/assets/santorini/SantoriniLogicNumba.py`.
+
+    fn position(workers: &[(usize, i8)], levels: &[(usize, i8)]) -> BoardState {
+        let mut bytes = [0i8; STATE_SIZE];
+        for &(square, worker) in workers {
+            bytes[square * 3] = worker;
+        }
+        for &(square, level) in levels {
+            bytes[square * 3 + 1] = level;
+        }
+        BoardState::from_bytes(&bytes)
+    }
+
+    #[test]
+    fn perft_from_empty_board() {
+        let board = BoardState::new();
+        let expected = [1, 25, 600, 13_800, 303_600];
+        for (depth, &nodes) in expected.iter().enumerate() {
+            assert_eq!(board.perft(0, depth as u32), nodes, "depth {depth}");
+        }
+    }
+
+    #[test]
+    fn perft_into_movement_phase() {
+        let board = BoardState::new();
+        let first = board.divide(0, 5);
+        assert_eq!(first.len(), 25);
+        assert_eq!(first[0], (0, 541_674));
+        let total: u64 = first.iter().map(|&(_, nodes)| nodes).sum();
+        assert_eq!(total, 17_252_928);
+    }
+
+    #[test]
+    fn perft_opening_position() {
+        let board = position(&[(6, 1), (18, 2), (8, -1), (16, -2)], &[]);
+        assert_eq!(board.perft(0, 1), 80);
+        assert_eq!(board.perft(0, 2), 6_176);
+        assert_eq!(board.perft(0, 3), 426_384);
+    }
+
+    #[test]
+    fn perft_stops_at_finished_games() {
+        let board = position(
+            &[(6, 1), (12, 2), (8, -1), (17, -2)],
+            &[
+                (6, 2),
+                (7, 1),
+                (11, 2),
+                (13, 3),
+                (2, 4),
+                (16, 1),
+                (18, 2),
+                (0, 3),
+            ],
+        );
+        let expected = [40, 1_608, 64_572];
+        for (depth, &nodes) in expected.iter().enumerate() {
+            assert_eq!(
+                board.perft(0, depth as u32 + 1),
+                nodes,
+                "depth {}",
+                depth + 1
+            );
+        }
+        let total: u64 = board.divide(0, 2).iter().map(|&(_, nodes)| nodes).sum();
+        assert_eq!(total, expected[1]);
+    }
+}
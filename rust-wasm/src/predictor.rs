@@ -1,4 +1,13 @@
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+
 use serde::Deserialize;
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+
+use crate::board::{ACTION_SIZE, STATE_SIZE};
+use crate::evaluator::AsyncEvaluator;
 
 /// Shape of the object resolved by the JavaScript/TypeScript predictor Promise.
 #[derive(Debug, Deserialize)]
@@ -8,3 +17,140 @@ pub struct NetworkPrediction {
     /// Scalar evaluation in [-1.0, 1.0] from the perspective of the side-to-move.
     pub v: f32,
 }
+
+/// Split what the batch predictor Promise resolved to into one [`NetworkPrediction`] per board.
+/// The value holds the stacked network outputs for `count` boards, i.e. `pi` holds
+/// `count × 162` scores row by row and `v` holds `count` values. Either field may be a
+/// `Float32Array` or a plain number array.
+pub fn split_batch(value: JsValue, count: usize) -> Result<Vec<NetworkPrediction>, JsValue> {
+    let pi = f32_vec(js_sys::Reflect::get(&value, &JsValue::from_str("pi"))?)?;
+    let v = f32_vec(js_sys::Reflect::get(&value, &JsValue::from_str("v"))?)?;
+    if pi.len() < count * ACTION_SIZE || v.len() < count {
+        return Err(JsValue::from_str(&format!(
+            "batch predictor returned {} policy entries and {} values for {count} boards",
+            pi.len(),
+            v.len()
+        )));
+    }
+    Ok(pi
+        .chunks_exact(ACTION_SIZE)
+        .zip(v)
+        .take(count)
+        .map(|(pi, v)| NetworkPrediction { pi: pi.to_vec(), v })
+        .collect())
+}
+
+fn f32_vec(value: JsValue) -> Result<Vec<f32>, JsValue> {
+    match value.dyn_ref::<js_sys::Float32Array>() {
+        Some(array) => Ok(array.to_vec()),
+        None => Ok(serde_wasm_bindgen::from_value(value)?),
+    }
+}
+
+/// The JavaScript predictor functions registered on `SantoriniMcts`, as an [`AsyncEvaluator`].
+pub struct JsPredictor {
+    /// `(board: Int8Array, mask: Uint8Array) => Promise<{ pi, v }>`.
+    pub predictor: js_sys::Function,
+    /// Optional `(boards, masks, count) => Promise<{ pi, v }>`, see `setBatchPredictor`.
+    pub batch_predictor: Option<js_sys::Function>,
+}
+
+/// Whatever a predictor threw or rejected with, passed back to JavaScript untouched.
+#[derive(Debug)]
+pub struct JsError(pub JsValue);
+
+impl fmt::Display for JsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0.as_string() {
+            Some(message) => f.write_str(&message),
+            None => write!(f, "{:?}", self.0),
+        }
+    }
+}
+
+impl std::error::Error for JsError {}
+
+impl From<JsValue> for JsError {
+    fn from(value: JsValue) -> Self {
+        Self(value)
+    }
+}
+
+impl From<serde_wasm_bindgen::Error> for JsError {
+    fn from(err: serde_wasm_bindgen::Error) -> Self {
+        Self(err.into())
+    }
+}
+
+/// Shape of what the predictor Promise resolves to.
+enum ResponseShape {
+    /// `{ pi, v }` for a single board.
+    Single,
+    /// `{ pi, v }` holding `count` stacked rows, from the batch predictor.
+    Stacked,
+    /// `[{ pi, v }, ...]` joined from concurrent single-board calls.
+    Joined,
+}
+
+impl JsPredictor {
+    /// Call into JavaScript. Batches go through the batch predictor in a single call when one is
+    /// registered, otherwise through concurrent single-board calls.
+    fn call(
+        &self,
+        boards: &[i8],
+        valid: &[u8],
+        count: usize,
+    ) -> Result<(js_sys::Promise, ResponseShape), JsValue> {
+        // Copies rather than views: wasm memory may grow, and another run may restage the
+        // buffers, while the Promise is pending.
+        let (value, shape) = if count == 1 && self.batch_predictor.is_none() {
+            let board_js = JsValue::from(js_sys::Int8Array::from(boards));
+            let mask_js = JsValue::from(js_sys::Uint8Array::from(valid));
+
+            let value = self.predictor.call2(&JsValue::NULL, &board_js, &mask_js)?;
+            (value, ResponseShape::Single)
+        } else if let Some(batch_predictor) = &self.batch_predictor {
+            let boards = JsValue::from(js_sys::Int8Array::from(boards));
+            let masks = JsValue::from(js_sys::Uint8Array::from(valid));
+            let value =
+                batch_predictor.call3(&JsValue::NULL, &boards, &masks, &JsValue::from(count))?;
+            (value, ResponseShape::Stacked)
+        } else {
+            let calls = js_sys::Array::new();
+            for (board, mask) in boards
+                .chunks_exact(STATE_SIZE)
+                .zip(valid.chunks_exact(ACTION_SIZE))
+                .take(count)
+            {
+                let board_js = JsValue::from(js_sys::Int8Array::from(board));
+                let mask_js = JsValue::from(js_sys::Uint8Array::from(mask));
+                calls.push(&self.predictor.call2(&JsValue::NULL, &board_js, &mask_js)?);
+            }
+            (
+                JsValue::from(js_sys::Promise::all(&calls)),
+                ResponseShape::Joined,
+            )
+        };
+        Ok((js_sys::Promise::from(value), shape))
+    }
+}
+
+impl AsyncEvaluator for JsPredictor {
+    type Error = JsError;
+    type Pending = Pin<Box<dyn Future<Output = Result<Vec<NetworkPrediction>, JsError>>>>;
+
+    fn request(&mut self, boards: &[i8], valid: &[u8], count: usize) -> Self::Pending {
+        let called = self.call(boards, valid, count);
+        Box::pin(async move {
+            let (promise, shape) = called?;
+            let resolved = JsFuture::from(promise).await?;
+            Ok(match shape {
+                ResponseShape::Single => vec![serde_wasm_bindgen::from_value::<NetworkPrediction>(
+                    resolved,
+                )?],
+                ResponseShape::Stacked => split_batch(resolved, count)?,
+                ResponseShape::Joined => serde_wasm_bindgen::from_value(resolved)?,
+            })
+        })
+    }
+}
@@ -0,0 +1,131 @@
+//! Compile-time geometry tables shared by move generation, action encoding and decoding. Every
+//! bounds check on the 5×5 grid is resolved here once, so the hot paths only index arrays.
+
+use crate::board::{BOARD_SIZE, CELL_COUNT};
+
+/// Direction index meaning "stay in place"; never legal for either the move or the build step.
+pub const NO_MOVE: usize = 4;
+pub const NO_BUILD: usize = 4;
+
+/// Marker stored in [`STEP`] for moves that leave the board.
+const OFF_BOARD: u8 = u8::MAX;
+
+/// `(dy, dx)` for each of the nine direction indices used by the action encoding.
+const DIRECTIONS: [(i8, i8); 9] = [
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+    (0, -1),
+    (0, 0),
+    (0, 1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+];
+
+/// `STEP[square][direction]` is the square reached from `square`, or [`OFF_BOARD`].
+const STEP: [[u8; 9]; CELL_COUNT] = build_step();
+
+/// `NEIGHBOURS[square]` has a bit set for each of the (up to eight) squares adjacent to `square`.
+pub const NEIGHBOURS: [u32; CELL_COUNT] = build_neighbours();
+
+/// `DIRECTION_BETWEEN[from][to]` is the direction index leading from `from` to `to`. Only
+/// meaningful for adjacent squares (or `from == to`, which yields [`NO_MOVE`]).
+const DIRECTION_BETWEEN: [[u8; CELL_COUNT]; CELL_COUNT] = build_direction_between();
+
+/// Square reached from `square` in `direction`, if it stays on the board.
+#[inline]
+pub const fn step(square: usize, direction: usize) -> Option<usize> {
+    match STEP[square][direction] {
+        OFF_BOARD => None,
+        target => Some(target as usize),
+    }
+}
+
+/// Direction index leading from `from` to the adjacent square `to`.
+#[inline]
+pub const fn direction_between(from: usize, to: usize) -> usize {
+    DIRECTION_BETWEEN[from][to] as usize
+}
+
+const fn build_step() -> [[u8; 9]; CELL_COUNT] {
+    let mut table = [[OFF_BOARD; 9]; CELL_COUNT];
+    let mut square = 0;
+    while square < CELL_COUNT {
+        let (y, x) = ((square / BOARD_SIZE) as i32, (square % BOARD_SIZE) as i32);
+        let mut direction = 0;
+        while direction < 9 {
+            let (dy, dx) = DIRECTIONS[direction];
+            let (ny, nx) = (y + dy as i32, x + dx as i32);
+            if ny >= 0 && nx >= 0 && ny < BOARD_SIZE as i32 && nx < BOARD_SIZE as i32 {
+                table[square][direction] = (ny as usize * BOARD_SIZE + nx as usize) as u8;
+            }
+            direction += 1;
+        }
+        square += 1;
+    }
+    table
+}
+
+const fn build_neighbours() -> [u32; CELL_COUNT] {
+    let mut table = [0u32; CELL_COUNT];
+    let mut square = 0;
+    while square < CELL_COUNT {
+        let mut direction = 0;
+        while direction < 9 {
+            let target = STEP[square][direction];
+            if direction != NO_MOVE && target != OFF_BOARD {
+                table[square] |= 1 << target;
+            }
+            direction += 1;
+        }
+        square += 1;
+    }
+    table
+}
+
+const fn build_direction_between() -> [[u8; CELL_COUNT]; CELL_COUNT] {
+    let mut table = [[NO_MOVE as u8; CELL_COUNT]; CELL_COUNT];
+    let mut square = 0;
+    while square < CELL_COUNT {
+        let mut direction = 0;
+        while direction < 9 {
+            let target = STEP[square][direction];
+            if target != OFF_BOARD {
+                table[square][target as usize] = direction as u8;
+            }
+            direction += 1;
+        }
+        square += 1;
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tables_agree_with_direction_offsets() {
+        for (square, &neighbours) in NEIGHBOURS.iter().enumerate() {
+            let (y, x) = ((square / BOARD_SIZE) as i8, (square % BOARD_SIZE) as i8);
+            for (direction, (dy, dx)) in DIRECTIONS.iter().enumerate() {
+                let (ny, nx) = (y + dy, x + dx);
+                let expected = ((0..5).contains(&ny) && (0..5).contains(&nx))
+                    .then(|| ny as usize * BOARD_SIZE + nx as usize);
+                assert_eq!(step(square, direction), expected);
+                if let Some(target) = expected {
+                    assert_eq!(direction_between(square, target), direction);
+                    let adjacent = neighbours & (1 << target) != 0;
+                    assert_eq!(adjacent, direction != NO_MOVE);
+                }
+            }
+            assert_eq!(
+                neighbours.count_ones() as usize,
+                (0..9)
+                    .filter(|&d| d != NO_MOVE && step(square, d).is_some())
+                    .count()
+            );
+        }
+    }
+}
@@ -0,0 +1,87 @@
+//! Zobrist keys for incremental 64-bit position hashing, plus the identity hasher used by the
+//! search tree (keys are already uniformly distributed, so re-hashing them is wasted work).
+
+use std::hash::{BuildHasherDefault, Hasher};
+
+use crate::board::CELL_COUNT;
+
+/// `WORKER_KEYS[slot][square]`, slots in `1, 2, -1, -2` order.
+const WORKER_KEYS: [[u64; CELL_COUNT]; 4] = build_table::<4>(0x5eed_0001);
+/// `LEVEL_KEYS[level][square]` for levels `1..=4`; level 0 hashes to zero.
+const LEVEL_KEYS: [[u64; CELL_COUNT]; 5] = build_table::<5>(0x5eed_0002);
+/// Toggled whenever the turn passes to the other player.
+pub const SIDE_KEY: u64 = splitmix64(0x5eed_0004).1;
+
+#[inline]
+pub const fn worker_key(slot: usize, square: usize) -> u64 {
+    WORKER_KEYS[slot][square]
+}
+
+/// Key for `square` standing at `level`. Out-of-range levels (only possible on unvalidated boards)
+/// hash like the nearest legal level.
+#[inline]
+pub const fn level_key(level: i8, square: usize) -> u64 {
+    let level = if level < 0 {
+        0
+    } else if level > 4 {
+        4
+    } else {
+        level as usize
+    };
+    if level == 0 {
+        0
+    } else {
+        LEVEL_KEYS[level][square]
+    }
+}
+
+/// SplitMix64 step: returns the advanced state and the mixed output.
+const fn splitmix64(state: u64) -> (u64, u64) {
+    let state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    (state, z ^ (z >> 31))
+}
+
+const fn build_table<const ROWS: usize>(seed: u64) -> [[u64; CELL_COUNT]; ROWS] {
+    let mut table = [[0u64; CELL_COUNT]; ROWS];
+    let mut state = seed;
+    let mut row = 0;
+    while row < ROWS {
+        let mut square = 0;
+        while square < CELL_COUNT {
+            let (next, value) = splitmix64(state);
+            state = next;
+            table[row][square] = value;
+            square += 1;
+        }
+        row += 1;
+    }
+    table
+}
+
+/// Pass-through hasher for tables keyed by Zobrist hashes.
+#[derive(Default, Clone, Copy)]
+pub struct ZobristHasher(u64);
+
+impl Hasher for ZobristHasher {
+    #[inline]
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    #[inline]
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = self.0.rotate_left(8) ^ u64::from(byte);
+        }
+    }
+
+    #[inline]
+    fn write_u64(&mut self, value: u64) {
+        self.0 = value;
+    }
+}
+
+pub type BuildZobristHasher = BuildHasherDefault<ZobristHasher>;